@@ -0,0 +1,422 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Control-flow graph of a function body and the analyses built on it:
+// dominator and post-dominator trees, dominance frontiers and natural loops.
+
+use std::collections::HashMap;
+
+use crate::{BasicBlock, Instruction};
+
+impl Instruction {
+    /// Whether the instruction ends a block.
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Br { .. }
+                | Instruction::CondBr { .. }
+                | Instruction::Ret { .. }
+                | Instruction::Switch { .. }
+                | Instruction::Unreachable
+                | Instruction::Unhandled { .. }
+        )
+    }
+
+    /// Labels a terminator branches to, in branch order, with repeats.
+    pub fn targets(&self) -> Vec<&str> {
+        match self {
+            Instruction::Br {
+                target_true,
+                target_false,
+                ..
+            } => std::iter::once(target_true)
+                .chain(target_false)
+                .map(String::as_str)
+                .collect(),
+            Instruction::CondBr {
+                then_target,
+                else_target,
+                ..
+            } => vec![then_target, else_target],
+            Instruction::Switch { default, cases, .. } => std::iter::once(default.as_str())
+                .chain(cases.iter().map(|(_, target)| target.as_str()))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Point every edge of a terminator that goes to `from` at `to`.
+    pub fn retarget(&mut self, from: &str, to: &str) {
+        let swap = |label: &mut String| {
+            if label == from {
+                *label = to.to_string();
+            }
+        };
+        match self {
+            Instruction::Br {
+                target_true,
+                target_false,
+                ..
+            } => {
+                swap(target_true);
+                if let Some(f) = target_false {
+                    swap(f);
+                }
+            }
+            Instruction::CondBr {
+                then_target,
+                else_target,
+                ..
+            } => {
+                swap(then_target);
+                swap(else_target);
+            }
+            Instruction::Switch { default, cases, .. } => {
+                swap(default);
+                for (_, target) in cases {
+                    swap(target);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Edges between the blocks of a function, identified by their position.
+/// The first block is the entry.
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    labels: Vec<String>,
+    index: HashMap<String, usize>,
+    succs: Vec<Vec<usize>>,
+    preds: Vec<Vec<usize>>,
+}
+
+impl Cfg {
+    /// Build the graph from the block terminators. Branches to labels that
+    /// are not in `blocks` are ignored.
+    pub fn new(blocks: &[BasicBlock]) -> Self {
+        let labels: Vec<String> = blocks.iter().map(|block| block.name.clone()).collect();
+        let index: HashMap<String, usize> = labels
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
+        let succs: Vec<Vec<usize>> = blocks
+            .iter()
+            .map(|block| {
+                let mut succs: Vec<usize> = vec![];
+                for target in block
+                    .instrs
+                    .last()
+                    .map(Instruction::targets)
+                    .unwrap_or_default()
+                {
+                    if let Some(&t) = index.get(target)
+                        && !succs.contains(&t)
+                    {
+                        succs.push(t);
+                    }
+                }
+                succs
+            })
+            .collect();
+        let preds = reverse(&succs);
+        Cfg {
+            labels,
+            index,
+            succs,
+            preds,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Position of the block labelled `label`.
+    pub fn index(&self, label: &str) -> Option<usize> {
+        self.index.get(label).copied()
+    }
+
+    pub fn label(&self, block: usize) -> &str {
+        &self.labels[block]
+    }
+
+    /// Distinct successors, in branch order.
+    pub fn successors(&self, block: usize) -> &[usize] {
+        &self.succs[block]
+    }
+
+    /// Distinct predecessors, in block order.
+    pub fn predecessors(&self, block: usize) -> &[usize] {
+        &self.preds[block]
+    }
+
+    /// Blocks reachable from the entry, in reverse post-order.
+    pub fn reverse_post_order(&self) -> Vec<usize> {
+        reverse_post_order(&self.succs, &[0])
+    }
+
+    pub fn dominators(&self) -> DomTree {
+        DomTree::new(&self.succs, &self.preds, &[0])
+    }
+
+    /// Post-dominators, over the reversed graph from every block without
+    /// successors. Blocks that never reach one, such as the body of an
+    /// infinite loop, are not in the tree.
+    pub fn post_dominators(&self) -> DomTree {
+        let exits: Vec<usize> = (0..self.len())
+            .filter(|&b| self.succs[b].is_empty())
+            .collect();
+        DomTree::new(&self.preds, &self.succs, &exits)
+    }
+
+    /// Natural loops, one per header, outer loops before the loops they
+    /// contain.
+    pub fn loops(&self) -> Vec<Loop> {
+        let doms = self.dominators();
+        let mut loops: Vec<Loop> = vec![];
+        for header in doms.order().iter().copied() {
+            let latches: Vec<usize> = self.preds[header]
+                .iter()
+                .copied()
+                .filter(|&p| doms.dominates(header, p))
+                .collect();
+            if latches.is_empty() {
+                continue;
+            }
+            let mut blocks = vec![header];
+            let mut work = latches.clone();
+            while let Some(b) = work.pop() {
+                if blocks.contains(&b) || !doms.contains(b) {
+                    continue;
+                }
+                blocks.push(b);
+                work.extend(&self.preds[b]);
+            }
+            blocks.sort_unstable();
+            loops.push(Loop {
+                header,
+                latches,
+                blocks,
+            });
+        }
+        loops
+    }
+}
+
+/// A natural loop: the blocks that reach a back edge to `header` without
+/// going through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    /// Sources of the back edges.
+    pub latches: Vec<usize>,
+    /// Every block of the loop, the header included, in block order.
+    pub blocks: Vec<usize>,
+}
+
+impl Loop {
+    pub fn contains(&self, block: usize) -> bool {
+        self.blocks.binary_search(&block).is_ok()
+    }
+}
+
+/// Dominator tree, or post-dominator tree when built on the reversed graph.
+#[derive(Debug, Clone)]
+pub struct DomTree {
+    /// Immediate dominator; `None` for roots and blocks outside the tree.
+    idom: Vec<Option<usize>>,
+    /// Blocks in the tree, in reverse post-order of the graph walked.
+    order: Vec<usize>,
+    /// Predecessors in the graph walked.
+    preds: Vec<Vec<usize>>,
+}
+
+impl DomTree {
+    /// Cooper, Harvey and Kennedy, "A Simple, Fast Dominance Algorithm",
+    /// with a virtual root above `roots`.
+    fn new(succs: &[Vec<usize>], preds: &[Vec<usize>], roots: &[usize]) -> Self {
+        let order = reverse_post_order(succs, roots);
+        let root = succs.len();
+        let mut position = vec![usize::MAX; root + 1];
+        for (i, &b) in order.iter().enumerate() {
+            position[b] = i + 1;
+        }
+        position[root] = 0;
+
+        let mut idom: Vec<Option<usize>> = vec![None; root + 1];
+        idom[root] = Some(root);
+        for &r in roots {
+            idom[r] = Some(root);
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &b in &order {
+                if roots.contains(&b) {
+                    continue;
+                }
+                let mut new: Option<usize> = None;
+                for &p in &preds[b] {
+                    if idom[p].is_none() {
+                        continue;
+                    }
+                    new = Some(match new {
+                        None => p,
+                        Some(mut a) => {
+                            let mut p = p;
+                            while a != p {
+                                while position[a] > position[p] {
+                                    a = idom[a].unwrap_or(root);
+                                }
+                                while position[p] > position[a] {
+                                    p = idom[p].unwrap_or(root);
+                                }
+                            }
+                            a
+                        }
+                    });
+                }
+                if new.is_some() && idom[b] != new {
+                    idom[b] = new;
+                    changed = true;
+                }
+            }
+        }
+        idom.truncate(root);
+        for d in idom.iter_mut() {
+            if *d == Some(root) {
+                *d = None;
+            }
+        }
+        DomTree {
+            idom,
+            order,
+            preds: preds.to_vec(),
+        }
+    }
+
+    /// Immediate dominator of `block`.
+    pub fn idom(&self, block: usize) -> Option<usize> {
+        self.idom[block]
+    }
+
+    /// Whether `block` is in the tree, that is reachable from a root.
+    pub fn contains(&self, block: usize) -> bool {
+        self.order.contains(&block)
+    }
+
+    /// Blocks in the tree, parents before children.
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Blocks `block` immediately dominates, in [`order`](Self::order).
+    pub fn children(&self, block: usize) -> Vec<usize> {
+        self.order
+            .iter()
+            .copied()
+            .filter(|&c| self.idom[c] == Some(block))
+            .collect()
+    }
+
+    /// Whether `a` dominates `b`; every block in the tree dominates itself.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        if !self.contains(b) {
+            return false;
+        }
+        let mut runner = Some(b);
+        while let Some(r) = runner {
+            if r == a {
+                return true;
+            }
+            runner = self.idom[r];
+        }
+        false
+    }
+
+    /// Dominance frontier of every block: the blocks it does not strictly
+    /// dominate but one of whose predecessors it dominates.
+    pub fn frontiers(&self) -> Vec<Vec<usize>> {
+        let mut frontiers: Vec<Vec<usize>> = vec![vec![]; self.idom.len()];
+        for &b in &self.order {
+            let preds: Vec<usize> = self.preds[b]
+                .iter()
+                .copied()
+                .filter(|&p| self.contains(p))
+                .collect();
+            if preds.len() < 2 {
+                continue;
+            }
+            for p in preds {
+                let mut runner = Some(p);
+                while let Some(r) = runner
+                    && Some(r) != self.idom[b]
+                {
+                    if !frontiers[r].contains(&b) {
+                        frontiers[r].push(b);
+                    }
+                    runner = self.idom[r];
+                }
+            }
+        }
+        frontiers
+    }
+}
+
+fn reverse(succs: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut preds = vec![vec![]; succs.len()];
+    for (b, targets) in succs.iter().enumerate() {
+        for &t in targets {
+            preds[t].push(b);
+        }
+    }
+    preds
+}
+
+/// Blocks reachable from `roots`, in reverse post-order of a depth-first
+/// walk starting from them in order.
+fn reverse_post_order(succs: &[Vec<usize>], roots: &[usize]) -> Vec<usize> {
+    let mut post = vec![];
+    let mut seen = vec![false; succs.len()];
+    for &root in roots.iter().rev() {
+        if root >= succs.len() || seen[root] {
+            continue;
+        }
+        seen[root] = true;
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        while let Some((b, next)) = stack.pop() {
+            if let Some(&s) = succs[b].get(next) {
+                stack.push((b, next + 1));
+                if !seen[s] {
+                    seen[s] = true;
+                    stack.push((s, 0));
+                }
+            } else {
+                post.push(b);
+            }
+        }
+    }
+    post.reverse();
+    post
+}
@@ -15,207 +15,897 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod cfg;
+pub mod mem2reg;
+
+use std::collections::HashMap;
+
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+/// LLVM first-class and aggregate types as seen by the backend.
+///
+/// Integer types are signless, exactly like in LLVM: signedness is a
+/// property of the instruction (`SDiv` vs `UDiv`), not of the value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Type {
+    Void,
+    /// Integer of arbitrary bit width (`i1`, `i8`, `i32`, `i64`, ...).
+    Int(u32),
+    Half,
+    Float,
+    Double,
+    Ptr {
+        pointee: Box<Type>,
+        addr_space: u32,
+    },
+    Vector {
+        elem: Box<Type>,
+        len: usize,
+    },
+    Array {
+        elem: Box<Type>,
+        len: usize,
+    },
+    /// Literal (anonymous) structure type.
+    Struct {
+        fields: Vec<Type>,
+        packed: bool,
+    },
+    /// Named structure type, e.g. `%struct.float3`. Kept by name so that
+    /// recursive definitions stay finite.
+    NamedStruct(String),
+    Function {
+        ret: Box<Type>,
+        params: Vec<Type>,
+        var_arg: bool,
+    },
+    Label,
+    /// Any type the backend does not model (metadata, token, x86_mmx...).
+    Other(String),
+}
+
+impl Type {
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Int(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Half | Type::Float | Type::Double)
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Ptr { .. })
+    }
+
+    /// Integer width in bits, or `None` for non-integer types.
+    pub fn int_bits(&self) -> Option<u32> {
+        match self {
+            Type::Int(bits) => Some(*bits),
+            _ => None,
+        }
+    }
+
+    /// Pointee type of a pointer, or `None` for non-pointer types.
+    pub fn pointee(&self) -> Option<&Type> {
+        match self {
+            Type::Ptr { pointee, .. } => Some(pointee),
+            _ => None,
+        }
+    }
+
+    /// Address space of a pointer, or `None` for non-pointer types.
+    pub fn addr_space(&self) -> Option<u32> {
+        match self {
+            Type::Ptr { addr_space, .. } => Some(*addr_space),
+            _ => None,
+        }
+    }
+}
+
+/// A typed operand: an SSA local, a global symbol or a constant.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Value {
+    /// SSA register or function parameter (`%x`), without the sigil.
+    Local {
+        name: String,
+        ty: Type,
+    },
+    /// Global variable or function (`@g`), without the sigil. The type is
+    /// the type of the reference itself, i.e. a pointer.
+    Global {
+        name: String,
+        ty: Type,
+    },
+    /// Integer constant. `value` holds the raw bits, zero-extended to 64.
+    ConstInt {
+        value: u64,
+        ty: Type,
+    },
+    ConstFloat {
+        value: f64,
+        ty: Type,
+    },
+    Null {
+        ty: Type,
+    },
+    Undef {
+        ty: Type,
+    },
+    /// Constant the IR model cannot represent yet (aggregates, constant
+    /// expressions...), kept as LLVM text for diagnostics.
+    Unhandled {
+        text: String,
+        ty: Type,
+    },
+}
+
+impl Value {
+    pub fn ty(&self) -> &Type {
+        match self {
+            Value::Local { ty, .. }
+            | Value::Global { ty, .. }
+            | Value::ConstInt { ty, .. }
+            | Value::ConstFloat { ty, .. }
+            | Value::Null { ty }
+            | Value::Undef { ty }
+            | Value::Unhandled { ty, .. } => ty,
+        }
+    }
+
+    /// Name of the function whose address the value is.
+    pub fn function_address(&self) -> Option<&str> {
+        match self {
+            Value::Global { name, ty } if matches!(ty.pointee(), Some(Type::Function { .. })) => {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Name of a local or global value, `None` for constants.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Value::Local { name, .. } | Value::Global { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Name of a local value, `None` for globals and constants.
+    pub fn local_name(&self) -> Option<&str> {
+        match self {
+            Value::Local { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        !matches!(self, Value::Local { .. } | Value::Global { .. })
+    }
+
+    /// Integer constant sign-extended from its bit width.
+    pub fn as_signed(&self) -> Option<i64> {
+        match self {
+            Value::ConstInt { value, ty } => {
+                let bits = ty.int_bits().unwrap_or(64);
+                if bits == 0 || bits >= 64 {
+                    Some(*value as i64)
+                } else {
+                    let shift = 64 - bits;
+                    Some(((*value << shift) as i64) >> shift)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Target of a call.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Callee {
+    /// Function called by name.
+    Direct(String),
+    /// Function pointer held in a value.
+    Indirect(Value),
+}
+
+/// Formal parameters and return type of a function.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Signature {
+    pub params: Vec<Value>,
+    /// Pointee type of the parameters passed `byval`, in step with
+    /// `params`.
+    pub byval: Vec<Option<Type>>,
+    pub ret: Type,
+}
+
+impl Signature {
+    /// Pointee type of parameter `index` if it is passed `byval`.
+    pub fn byval(&self, index: usize) -> Option<&Type> {
+        self.byval.get(index).and_then(Option::as_ref)
+    }
+}
+
+/// Initial value of a global variable.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Initializer {
+    /// Integer, float, null pointer or address of another global.
+    Scalar(Value),
+    /// `zeroinitializer`, or `undef` inside an aggregate.
+    Zero(Type),
+    /// Array, vector or structure, element by element.
+    Aggregate(Type, Vec<Initializer>),
+}
+
+/// Module-level variable (`@g = global ...`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GlobalVar {
+    pub name: String,
+    /// Type of the variable itself, not of the pointer to it.
+    pub ty: Type,
+    pub addr_space: u32,
+    /// Alignment in bytes, 0 when the IR gives none.
+    pub align: u32,
+    pub constant: bool,
+    /// `None` for declarations and `undef` variables, such as `__shared__`
+    /// buffers.
+    pub init: Option<Initializer>,
+}
+
+impl GlobalVar {
+    /// Type of a reference to the variable.
+    pub fn pointer_type(&self) -> Type {
+        Type::Ptr {
+            pointee: Box::new(self.ty.clone()),
+            addr_space: self.addr_space,
+        }
+    }
+}
+
+/// Calling convention of a function, as far as the backend tells them
+/// apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CallingConv {
+    C,
+    /// `ptx_kernel`: an entry point launched from the host.
+    PtxKernel,
+    /// `ptx_device`: a function called from the device.
+    PtxDevice,
+    Other,
+}
+
+/// Labelled straight-line sequence of instructions, ending in a terminator.
+#[derive(Debug, Clone, Serialize)]
+pub struct BasicBlock {
+    pub name: String,
+    pub instrs: Vec<Instruction>,
+}
+
+impl BasicBlock {
+    pub fn new(name: impl Into<String>, instrs: Vec<Instruction>) -> Self {
+        BasicBlock {
+            name: name.into(),
+            instrs,
+        }
+    }
+}
+
+/// Function definition or, without blocks, declaration.
+#[derive(Debug, Clone, Serialize)]
+pub struct Function {
+    pub name: String,
+    /// Parameters and return type.
+    pub signature: Signature,
+    /// Function attributes, in LLVM spelling (`nounwind`, `"key"="value"`).
+    pub attributes: Vec<String>,
+    /// Emitted as an `.entry` point rather than a `.func`.
+    pub kernel: bool,
+    pub calling_conv: CallingConv,
+    /// Private or internal linkage: not visible outside the module.
+    pub internal: bool,
+    /// Body, entry block first; empty for a declaration.
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Function {
+    pub fn is_declaration(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Every instruction of the body, in block order.
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.blocks.iter().flat_map(|block| &block.instrs)
+    }
+}
+
+/// A translation unit: functions, variables and the layout of its types.
+#[derive(Debug, Clone, Serialize)]
+pub struct Module {
+    /// Definitions and declarations, in source order.
+    pub functions: Vec<Function>,
+    pub globals: Vec<GlobalVar>,
+    /// `target datalayout` string; empty when the module has none.
+    pub data_layout: String,
+    /// Bodies of the named struct types.
+    pub struct_types: HashMap<String, Type>,
+}
+
+impl Module {
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    /// Functions with a body.
+    pub fn definitions(&self) -> impl Iterator<Item = &Function> {
+        self.functions.iter().filter(|f| !f.is_declaration())
+    }
+
+    pub fn declarations(&self) -> impl Iterator<Item = &Function> {
+        self.functions.iter().filter(|f| f.is_declaration())
+    }
+}
+
+/// Axis of a three-dimensional special register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Dim {
+    X,
+    Y,
+    Z,
+}
+
+impl Dim {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dim::X => "x",
+            Dim::Y => "y",
+            Dim::Z => "z",
+        }
+    }
+}
+
+/// Special register read through an `llvm.nvvm.read.ptx.sreg.*` intrinsic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SpecialRegister {
+    /// Thread index within the block (`threadIdx`).
+    Tid(Dim),
+    /// Block dimensions (`blockDim`).
+    Ntid(Dim),
+    /// Block index within the grid (`blockIdx`).
+    Ctaid(Dim),
+    /// Grid dimensions (`gridDim`).
+    Nctaid(Dim),
+    LaneId,
+    WarpId,
+    NWarpId,
+    SmId,
+    NSmId,
+    GridId,
+    WarpSize,
+    Clock,
+    Clock64,
+    GlobalTimer,
+}
+
+impl SpecialRegister {
+    /// Parse the part of the intrinsic name after `llvm.nvvm.read.ptx.sreg.`,
+    /// e.g. `tid.x` or `laneid`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        use SpecialRegister::*;
+
+        if let Some((reg, dim)) = name.split_once('.') {
+            let dim = match dim {
+                "x" => Dim::X,
+                "y" => Dim::Y,
+                "z" => Dim::Z,
+                _ => return None,
+            };
+            return match reg {
+                "tid" => Some(Tid(dim)),
+                "ntid" => Some(Ntid(dim)),
+                "ctaid" => Some(Ctaid(dim)),
+                "nctaid" => Some(Nctaid(dim)),
+                _ => None,
+            };
+        }
+
+        match name {
+            "laneid" => Some(LaneId),
+            "warpid" => Some(WarpId),
+            "nwarpid" => Some(NWarpId),
+            "smid" => Some(SmId),
+            "nsmid" => Some(NSmId),
+            "gridid" => Some(GridId),
+            "warpsize" => Some(WarpSize),
+            "clock" => Some(Clock),
+            "clock64" => Some(Clock64),
+            "globaltimer" => Some(GlobalTimer),
+            _ => None,
+        }
+    }
+
+    /// Name as used by the intrinsic, which is also the PTX register name
+    /// without the `%`.
+    pub fn name(&self) -> String {
+        use SpecialRegister::*;
+
+        match self {
+            Tid(dim) => format!("tid.{}", dim.as_str()),
+            Ntid(dim) => format!("ntid.{}", dim.as_str()),
+            Ctaid(dim) => format!("ctaid.{}", dim.as_str()),
+            Nctaid(dim) => format!("nctaid.{}", dim.as_str()),
+            LaneId => "laneid".into(),
+            WarpId => "warpid".into(),
+            NWarpId => "nwarpid".into(),
+            SmId => "smid".into(),
+            NSmId => "nsmid".into(),
+            GridId => "gridid".into(),
+            WarpSize => "warpsize".into(),
+            Clock => "clock".into(),
+            Clock64 => "clock64".into(),
+            GlobalTimer => "globaltimer".into(),
+        }
+    }
+}
+
+/// Set of threads a memory operation or fence is ordered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MemoryScope {
+    /// Threads of the same block (`cta` in PTX).
+    Block,
+    /// Threads of the same device.
+    Device,
+    /// Every thread in the system, host included.
+    System,
+}
+
+/// Memory ordering of atomic operations and fences, as in LLVM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MemoryOrdering {
+    /// `monotonic` (and `unordered`): atomic, but no ordering.
+    Relaxed,
+    Acquire,
+    Release,
+    AcqRel,
+    SeqCst,
+}
+
+/// Operation of an `atomicrmw`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AtomicOp {
+    Xchg,
+    Add,
+    Sub,
+    And,
+    Nand,
+    Or,
+    Xor,
+    /// Signed maximum.
+    Max,
+    /// Signed minimum.
+    Min,
+    UMax,
+    UMin,
+    FAdd,
+    FSub,
+}
+
+/// What a thread does at a barrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BarrierKind {
+    /// Wait until the expected number of threads arrived.
+    Sync,
+    /// Signal arrival and go on without waiting.
+    Arrive,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum Instruction {
     Load {
-        function: String,
-        dst: String,
-        src: String,
+        dst: Value,
+        src: Value,
     },
     Store {
-        function: String,
-        dst: String,
-        value: String,
+        dst: Value,
+        value: Value,
     },
     Add {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     FAdd {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     FMul {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     Phi {
-        function: String,
-        dst: String,
-        incoming: Vec<(String, String)>,
+        dst: Value,
+        incoming: Vec<(String, Value)>,
     },
     ICmp {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
         op: String,
     },
     GetElementPtr {
-        function: String,
-        dst: String,
-        base: String,
-        index: String,
+        dst: Value,
+        base: Value,
+        /// Type the first index steps over.
+        source_ty: Type,
+        indices: Vec<Value>,
     },
     Alloca {
-        function: String,
-        dst: String,
-        ty: String,
+        dst: Value,
+        ty: Type,
         align: u32,
     },
     Br {
-        function: String,
-        cond: Option<String>,
+        cond: Option<Value>,
         target_true: String,
         target_false: Option<String>,
     },
     CondBr {
-        function: String,
-        cond: String,
+        cond: Value,
         then_target: String,
         else_target: String,
     },
     Ret {
-        function: String,
+        /// Returned value; `None` for `ret void`.
+        value: Option<Value>,
+    },
+    /// Multi-way branch on an integer. Case values are integer constants.
+    Switch {
+        cond: Value,
+        default: String,
+        cases: Vec<(Value, String)>,
     },
+    Unreachable,
     Sub {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     FSub {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     Mul {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     UDiv {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     SDiv {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     URem {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     SRem {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     FDiv {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     FRem {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
+    },
+    And {
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
+    },
+    Or {
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
+    },
+    Xor {
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
+    },
+    Shl {
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
+    },
+    /// Logical (zero-filling) shift right.
+    LShr {
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
+    },
+    /// Arithmetic (sign-filling) shift right.
+    AShr {
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
     },
     FCmp {
-        function: String,
-        dst: String,
-        lhs: String,
-        rhs: String,
+        dst: Value,
+        lhs: Value,
+        rhs: Value,
         op: String,
     },
     Select {
-        function: String,
-        dst: String,
-        cond: String,
-        val_true: String,
-        val_false: String,
+        dst: Value,
+        cond: Value,
+        val_true: Value,
+        val_false: Value,
     },
     Bitcast {
-        function: String,
-        dst: String,
-        src: String,
+        dst: Value,
+        src: Value,
     },
     ZExt {
-        function: String,
-        dst: String,
-        src: String,
+        dst: Value,
+        src: Value,
     },
     Trunc {
-        function: String,
-        dst: String,
-        src: String,
+        dst: Value,
+        src: Value,
+    },
+    SExt {
+        dst: Value,
+        src: Value,
+    },
+    FPToSI {
+        dst: Value,
+        src: Value,
+    },
+    FPToUI {
+        dst: Value,
+        src: Value,
+    },
+    SIToFP {
+        dst: Value,
+        src: Value,
+    },
+    UIToFP {
+        dst: Value,
+        src: Value,
+    },
+    FPExt {
+        dst: Value,
+        src: Value,
+    },
+    FPTrunc {
+        dst: Value,
+        src: Value,
+    },
+    PtrToInt {
+        dst: Value,
+        src: Value,
+    },
+    IntToPtr {
+        dst: Value,
+        src: Value,
+    },
+    /// Pointer conversion between address spaces; both spaces are carried
+    /// by the pointer types of `src` and `dst`.
+    AddrSpaceCast {
+        dst: Value,
+        src: Value,
     },
     Call {
-        function: String,
-        callee: String,
-        args: Vec<String>,
-        ret: Option<String>,
+        callee: Callee,
+        args: Vec<Value>,
+        /// Pointee type of the arguments passed `byval`, in step with
+        /// `args`.
+        byval: Vec<Option<Type>>,
+        ret: Option<Value>,
+    },
+    /// Read of a special register (thread and block indices, clocks...).
+    ReadSpecialReg {
+        dst: Value,
+        reg: SpecialRegister,
+    },
+    /// Named barrier among the threads of a block. `aligned` barriers are
+    /// executed by every thread of the warp at once, as `__syncthreads()`.
+    Barrier {
+        kind: BarrierKind,
+        aligned: bool,
+        id: Value,
+        /// Number of threads expected, or the whole block if `None`.
+        count: Option<Value>,
+    },
+    /// Legacy `membar` memory barrier, as the `llvm.nvvm.membar.*`
+    /// intrinsics.
+    MemBar {
+        scope: MemoryScope,
+    },
+    Fence {
+        ordering: MemoryOrdering,
+        scope: MemoryScope,
+    },
+    /// Atomic read-modify-write of `ptr`. `dst` receives the old value; the
+    /// backend drops it when nothing reads it.
+    AtomicRmw {
+        dst: Option<Value>,
+        op: AtomicOp,
+        ptr: Value,
+        value: Value,
+        ordering: MemoryOrdering,
+        scope: MemoryScope,
+    },
+    /// Atomic compare-and-swap. `dst` is the `{ old value, success }` pair
+    /// LLVM returns, taken apart with `ExtractValue`.
+    CmpXchg {
+        dst: Value,
+        ptr: Value,
+        expected: Value,
+        new: Value,
+        /// Ordering on success; PTX has no separate failure ordering.
+        ordering: MemoryOrdering,
+        scope: MemoryScope,
+    },
+    ExtractValue {
+        dst: Value,
+        aggregate: Value,
+        indices: Vec<u32>,
+    },
+    /// Register copy. Not produced from LLVM IR: out-of-SSA translation
+    /// inserts these when it replaces `Phi` nodes.
+    Copy {
+        dst: Value,
+        src: Value,
     },
     Unhandled {
-        function: String,
         text: String,
     },
 }
 
 impl Instruction {
-    pub fn function_name(&self) -> &str {
+    /// Every value referenced by the instruction, destination included.
+    pub fn values(&self) -> Vec<&Value> {
+        use Instruction::*;
+
         match self {
-            Instruction::Load { function, .. }
-            | Instruction::Store { function, .. }
-            | Instruction::Add { function, .. }
-            | Instruction::FAdd { function, .. }
-            | Instruction::FMul { function, .. }
-            | Instruction::Phi { function, .. }
-            | Instruction::ICmp { function, .. }
-            | Instruction::GetElementPtr { function, .. }
-            | Instruction::Alloca { function, .. }
-            | Instruction::Br { function, .. }
-            | Instruction::CondBr { function, .. }
-            | Instruction::Ret { function, .. }
-            | Instruction::Sub { function, .. }
-            | Instruction::FSub { function, .. }
-            | Instruction::Mul { function, .. }
-            | Instruction::UDiv { function, .. }
-            | Instruction::SDiv { function, .. }
-            | Instruction::URem { function, .. }
-            | Instruction::SRem { function, .. }
-            | Instruction::FDiv { function, .. }
-            | Instruction::FRem { function, .. }
-            | Instruction::FCmp { function, .. }
-            | Instruction::Select { function, .. }
-            | Instruction::Bitcast { function, .. }
-            | Instruction::ZExt { function, .. }
-            | Instruction::Trunc { function, .. }
-            | Instruction::Call { function, .. }
-            | Instruction::Unhandled { function, .. } => function,
+            // Arithmetic 3 operands
+            Add { dst, lhs, rhs, .. }
+            | Sub { dst, lhs, rhs, .. }
+            | Mul { dst, lhs, rhs, .. }
+            | UDiv { dst, lhs, rhs, .. }
+            | SDiv { dst, lhs, rhs, .. }
+            | URem { dst, lhs, rhs, .. }
+            | SRem { dst, lhs, rhs, .. }
+            | FAdd { dst, lhs, rhs, .. }
+            | FSub { dst, lhs, rhs, .. }
+            | FMul { dst, lhs, rhs, .. }
+            | FDiv { dst, lhs, rhs, .. }
+            | FRem { dst, lhs, rhs, .. }
+            | And { dst, lhs, rhs, .. }
+            | Or { dst, lhs, rhs, .. }
+            | Xor { dst, lhs, rhs, .. }
+            | Shl { dst, lhs, rhs, .. }
+            | LShr { dst, lhs, rhs, .. }
+            | AShr { dst, lhs, rhs, .. }
+            | ICmp { dst, lhs, rhs, .. }
+            | FCmp { dst, lhs, rhs, .. } => vec![dst, lhs, rhs],
+
+            // Load/Store
+            Load { dst, src, .. } => vec![dst, src],
+            Store { dst, value, .. } => vec![dst, value],
+
+            // Alloca
+            Alloca { dst, .. } | ReadSpecialReg { dst, .. } => vec![dst],
+
+            // GEP
+            GetElementPtr {
+                dst, base, indices, ..
+            } => {
+                let mut v = vec![dst, base];
+                v.extend(indices);
+                v
+            }
+
+            // Phi
+            Phi { dst, incoming, .. } => {
+                let mut v = vec![dst];
+                v.extend(incoming.iter().map(|(_, val)| val));
+                v
+            }
+
+            Select {
+                dst,
+                cond,
+                val_true,
+                val_false,
+                ..
+            } => {
+                vec![dst, cond, val_true, val_false]
+            }
+
+            Bitcast { dst, src, .. }
+            | ZExt { dst, src, .. }
+            | Trunc { dst, src, .. }
+            | SExt { dst, src, .. }
+            | FPToSI { dst, src, .. }
+            | FPToUI { dst, src, .. }
+            | SIToFP { dst, src, .. }
+            | UIToFP { dst, src, .. }
+            | FPExt { dst, src, .. }
+            | FPTrunc { dst, src, .. }
+            | PtrToInt { dst, src, .. }
+            | IntToPtr { dst, src, .. }
+            | AddrSpaceCast { dst, src, .. }
+            | Copy { dst, src, .. } => {
+                vec![dst, src]
+            }
+
+            // If `ret` is Some(x), then `x` will be assigned the return value of the call.
+            Call {
+                callee, args, ret, ..
+            } => {
+                let mut ops: Vec<&Value> = match callee {
+                    Callee::Indirect(target) => vec![target],
+                    Callee::Direct(_) => vec![],
+                };
+                ops.extend(args);
+                if let Some(r) = ret.as_ref() {
+                    ops.push(r);
+                }
+                ops
+            }
+
+            Barrier { id, count, .. } => {
+                let mut v = vec![id];
+                v.extend(count);
+                v
+            }
+
+            AtomicRmw {
+                dst, ptr, value, ..
+            } => {
+                let mut v: Vec<&Value> = dst.iter().collect();
+                v.extend([ptr, value]);
+                v
+            }
+            CmpXchg {
+                dst,
+                ptr,
+                expected,
+                new,
+                ..
+            } => vec![dst, ptr, expected, new],
+            ExtractValue { dst, aggregate, .. } => vec![dst, aggregate],
+
+            Br { cond, .. } => cond.iter().collect(),
+            CondBr { cond, .. } => vec![cond],
+            Switch { cond, cases, .. } => {
+                let mut v = vec![cond];
+                v.extend(cases.iter().map(|(value, _)| value));
+                v
+            }
+
+            Ret { value, .. } => value.iter().collect(),
+            Unreachable | MemBar { .. } | Fence { .. } | Unhandled { .. } => vec![],
         }
     }
 
-    pub fn used_operands(&self) -> Vec<&str> {
+    /// Mutable [`values`](Self::values), in the same order.
+    pub fn values_mut(&mut self) -> Vec<&mut Value> {
         use Instruction::*;
 
         match self {
@@ -232,6 +922,12 @@ impl Instruction {
             | FMul { dst, lhs, rhs, .. }
             | FDiv { dst, lhs, rhs, .. }
             | FRem { dst, lhs, rhs, .. }
+            | And { dst, lhs, rhs, .. }
+            | Or { dst, lhs, rhs, .. }
+            | Xor { dst, lhs, rhs, .. }
+            | Shl { dst, lhs, rhs, .. }
+            | LShr { dst, lhs, rhs, .. }
+            | AShr { dst, lhs, rhs, .. }
             | ICmp { dst, lhs, rhs, .. }
             | FCmp { dst, lhs, rhs, .. } => vec![dst, lhs, rhs],
 
@@ -240,20 +936,21 @@ impl Instruction {
             Store { dst, value, .. } => vec![dst, value],
 
             // Alloca
-            Alloca { dst, .. } => vec![dst],
+            Alloca { dst, .. } | ReadSpecialReg { dst, .. } => vec![dst],
 
             // GEP
             GetElementPtr {
-                dst, base, index, ..
-            } => vec![dst, base, index],
+                dst, base, indices, ..
+            } => {
+                let mut v = vec![dst, base];
+                v.extend(indices);
+                v
+            }
 
             // Phi
             Phi { dst, incoming, .. } => {
-                let mut v = vec![dst.as_str()];
-                for (label, val) in incoming {
-                    v.push(label);
-                    v.push(val);
-                }
+                let mut v = vec![dst];
+                v.extend(incoming.iter_mut().map(|(_, val)| val));
                 v
             }
 
@@ -267,35 +964,75 @@ impl Instruction {
                 vec![dst, cond, val_true, val_false]
             }
 
-            Bitcast { dst, src, .. } => {
-                vec![dst, src]
-            }
-            ZExt { dst, src, .. } => {
-                vec![dst, src]
-            }
-            Trunc { dst, src, .. } => {
+            Bitcast { dst, src, .. }
+            | ZExt { dst, src, .. }
+            | Trunc { dst, src, .. }
+            | SExt { dst, src, .. }
+            | FPToSI { dst, src, .. }
+            | FPToUI { dst, src, .. }
+            | SIToFP { dst, src, .. }
+            | UIToFP { dst, src, .. }
+            | FPExt { dst, src, .. }
+            | FPTrunc { dst, src, .. }
+            | PtrToInt { dst, src, .. }
+            | IntToPtr { dst, src, .. }
+            | AddrSpaceCast { dst, src, .. }
+            | Copy { dst, src, .. } => {
                 vec![dst, src]
             }
 
             // If `ret` is Some(x), then `x` will be assigned the return value of the call.
-            Call { args, ret, .. } => {
-                let mut ops: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-                if let Some(r) = ret.as_ref() {
+            Call {
+                callee, args, ret, ..
+            } => {
+                let mut ops: Vec<&mut Value> = match callee {
+                    Callee::Indirect(target) => vec![target],
+                    Callee::Direct(_) => vec![],
+                };
+                ops.extend(args);
+                if let Some(r) = ret.as_mut() {
                     ops.push(r);
                 }
                 ops
             }
 
-            CondBr {
-                cond,
-                then_target,
-                else_target,
+            Barrier { id, count, .. } => {
+                let mut v = vec![id];
+                v.extend(count);
+                v
+            }
+
+            AtomicRmw {
+                dst, ptr, value, ..
+            } => {
+                let mut v: Vec<&mut Value> = dst.iter_mut().collect();
+                v.extend([ptr, value]);
+                v
+            }
+            CmpXchg {
+                dst,
+                ptr,
+                expected,
+                new,
                 ..
-            } => vec![cond, then_target, else_target],
+            } => vec![dst, ptr, expected, new],
+            ExtractValue { dst, aggregate, .. } => vec![dst, aggregate],
+
+            Br { cond, .. } => cond.iter_mut().collect(),
+            CondBr { cond, .. } => vec![cond],
+            Switch { cond, cases, .. } => {
+                let mut v = vec![cond];
+                v.extend(cases.iter_mut().map(|(value, _)| value));
+                v
+            }
 
-            // Other
-            Unhandled { text, .. } => vec![text],
-            _ => vec![],
+            Ret { value, .. } => value.iter_mut().collect(),
+            Unreachable | MemBar { .. } | Fence { .. } | Unhandled { .. } => vec![],
         }
     }
+
+    /// Names of the locals and globals referenced by the instruction.
+    pub fn used_operands(&self) -> Vec<&str> {
+        self.values().into_iter().filter_map(Value::name).collect()
+    }
 }
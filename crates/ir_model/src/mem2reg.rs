@@ -0,0 +1,432 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Promotion of stack slots to SSA values: scalar replacement of small
+// aggregates, then SSA construction over the remaining scalar allocas.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::cfg::Cfg;
+use crate::{BasicBlock, Instruction, Type, Value};
+
+/// Aggregates with more elements than this are left in memory.
+const MAX_SROA_ELEMENTS: usize = 16;
+
+/// Rewrite the loads and stores of promotable allocas into SSA values.
+///
+/// Aggregate allocas only accessed through constant `getelementptr 0, k`
+/// are first split into one alloca per element. A scalar alloca whose
+/// address is only used to load and store whole values of its type is then
+/// removed: phis are placed on the iterated dominance frontier of its
+/// stores, and every load is replaced by the value reaching it, found by
+/// walking the dominator tree. Unused phis are dropped again.
+/// `struct_types` holds the bodies of the module's named structs.
+pub fn promote_allocas(
+    blocks: &[BasicBlock],
+    struct_types: &HashMap<String, Type>,
+) -> Vec<BasicBlock> {
+    let mut blocks = blocks.to_vec();
+    split_aggregates(&mut blocks, struct_types);
+
+    let instrs: Vec<&Instruction> = blocks.iter().flat_map(|block| &block.instrs).collect();
+    let slots: Vec<(String, Type)> = instrs
+        .iter()
+        .filter_map(|instr| match instr {
+            Instruction::Alloca { dst, ty, .. } if is_scalar(ty) => {
+                let name = dst.local_name()?;
+                promotable(name, ty, &instrs).then(|| (name.to_string(), ty.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    if slots.is_empty() {
+        return blocks;
+    }
+    let cfg = Cfg::new(&blocks);
+    let doms = cfg.dominators();
+    let frontiers = doms.frontiers();
+    let mut names: HashSet<String> = instrs
+        .iter()
+        .flat_map(|i| i.values())
+        .filter_map(|v| v.local_name().map(String::from))
+        .collect();
+
+    // Phis to insert at the start of each block, one per slot at most.
+    let mut phis: HashMap<usize, Vec<(usize, Value)>> = HashMap::new();
+    for (slot, (name, ty)) in slots.iter().enumerate() {
+        let mut work: Vec<usize> = blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| {
+                block.instrs.iter().any(|i| {
+                    matches!(i, Instruction::Store { dst, .. } if dst.local_name() == Some(name))
+                })
+            })
+            .map(|(b, _)| b)
+            .collect();
+        let mut placed: HashSet<usize> = HashSet::new();
+        while let Some(b) = work.pop() {
+            for &f in &frontiers[b] {
+                if !placed.insert(f) {
+                    continue;
+                }
+                let mut phi = format!("{name}.{}", blocks[f].name);
+                let mut n = 0;
+                while names.contains(&phi) {
+                    n += 1;
+                    phi = format!("{name}.{}.{n}", blocks[f].name);
+                }
+                names.insert(phi.clone());
+                phis.entry(f).or_default().push((
+                    slot,
+                    Value::Local {
+                        name: phi,
+                        ty: ty.clone(),
+                    },
+                ));
+                work.push(f);
+            }
+        }
+    }
+
+    // Walk the dominator tree, tracking the value each slot holds.
+    let slot_of = |value: &Value| {
+        let name = value.local_name()?;
+        slots.iter().position(|(slot, _)| slot == name)
+    };
+    let mut replaced: HashMap<String, Value> = HashMap::new();
+    let mut incoming: HashMap<usize, Vec<(usize, usize, Value)>> = HashMap::new();
+    let mut current: Vec<Value> = slots
+        .iter()
+        .map(|(_, ty)| Value::Undef { ty: ty.clone() })
+        .collect();
+    let mut kept: Vec<Vec<Instruction>> = vec![vec![]; blocks.len()];
+
+    enum Step {
+        Enter(usize),
+        Leave(Vec<Value>),
+    }
+    let mut stack = vec![Step::Enter(0)];
+    while let Some(step) = stack.pop() {
+        let b = match step {
+            Step::Enter(b) => b,
+            Step::Leave(saved) => {
+                current = saved;
+                continue;
+            }
+        };
+        stack.push(Step::Leave(current.clone()));
+        for (slot, phi) in phis.get(&b).into_iter().flatten() {
+            current[*slot] = phi.clone();
+        }
+        for instr in &blocks[b].instrs {
+            match instr {
+                Instruction::Alloca { dst, .. } if slot_of(dst).is_some() => {}
+                Instruction::Load { dst, src, .. } if let Some(slot) = slot_of(src) => {
+                    if let Some(name) = dst.local_name() {
+                        replaced.insert(name.to_string(), current[slot].clone());
+                    }
+                }
+                Instruction::Store { dst, value, .. } if let Some(slot) = slot_of(dst) => {
+                    current[slot] = value.clone();
+                }
+                other => kept[b].push(other.clone()),
+            }
+        }
+        for &succ in cfg.successors(b) {
+            for (slot, _) in phis.get(&succ).into_iter().flatten() {
+                incoming
+                    .entry(succ)
+                    .or_default()
+                    .push((*slot, b, current[*slot].clone()));
+            }
+        }
+        for &child in doms.children(b).iter().rev() {
+            stack.push(Step::Enter(child));
+        }
+    }
+
+    // Blocks the entry does not reach still drop the promoted accesses.
+    for (b, BasicBlock { instrs, .. }) in blocks.iter().enumerate() {
+        if doms.contains(b) {
+            continue;
+        }
+        for instr in instrs {
+            match instr {
+                Instruction::Alloca { dst, .. } | Instruction::Store { dst, .. }
+                    if slot_of(dst).is_some() => {}
+                Instruction::Load { dst, src, .. } if slot_of(src).is_some() => {
+                    if let Some(name) = dst.local_name() {
+                        replaced.insert(
+                            name.to_string(),
+                            Value::Undef {
+                                ty: dst.ty().clone(),
+                            },
+                        );
+                    }
+                }
+                other => kept[b].push(other.clone()),
+            }
+        }
+    }
+
+    // Incoming values in block order rather than the order of the walk.
+    for edges in incoming.values_mut() {
+        edges.sort_by_key(|(_, pred, _)| *pred);
+    }
+    let mut out: Vec<BasicBlock> = blocks
+        .iter()
+        .zip(kept)
+        .enumerate()
+        .map(|(b, (block, mut instrs))| {
+            let new_phis = phis
+                .get(&b)
+                .into_iter()
+                .flatten()
+                .map(|(slot, dst)| Instruction::Phi {
+                    dst: dst.clone(),
+                    incoming: incoming
+                        .get(&b)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(s, _, _)| s == slot)
+                        .map(|(_, pred, value)| (blocks[*pred].name.clone(), value.clone()))
+                        .collect(),
+                });
+            let at = instrs
+                .iter()
+                .take_while(|i| matches!(i, Instruction::Phi { .. }))
+                .count();
+            instrs.splice(at..at, new_phis);
+            BasicBlock::new(block.name.clone(), instrs)
+        })
+        .collect();
+
+    for BasicBlock { instrs, .. } in out.iter_mut() {
+        for instr in instrs.iter_mut() {
+            for value in instr.values_mut() {
+                if let Some(name) = value.local_name()
+                    && let Some(new) = resolve(&replaced, name)
+                {
+                    *value = new;
+                }
+            }
+        }
+    }
+    let inserted: HashSet<String> = phis
+        .values()
+        .flatten()
+        .filter_map(|(_, phi)| phi.local_name().map(String::from))
+        .collect();
+    remove_dead_phis(&mut out, &inserted);
+    out
+}
+
+/// Final value a replaced load stands for, following loads of loads.
+fn resolve(replaced: &HashMap<String, Value>, name: &str) -> Option<Value> {
+    let mut value = replaced.get(name)?;
+    while let Some(next) = value.local_name().and_then(|n| replaced.get(n)) {
+        value = next;
+    }
+    Some(value.clone())
+}
+
+fn is_scalar(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Int(_) | Type::Half | Type::Float | Type::Double | Type::Ptr { .. }
+    )
+}
+
+/// Whether every use of alloca `name` loads or stores a whole `ty`.
+pub fn promotable(name: &str, ty: &Type, instrs: &[&Instruction]) -> bool {
+    let is_slot = |value: &Value| value.local_name() == Some(name);
+    instrs.iter().all(|instr| match instr {
+        Instruction::Alloca { .. } => true,
+        Instruction::Load { dst, src, .. } if is_slot(src) => dst.ty() == ty,
+        Instruction::Store { dst, value, .. } if is_slot(dst) => {
+            value.ty() == ty && !is_slot(value)
+        }
+        other => !other.values().into_iter().any(is_slot),
+    })
+}
+
+/// Split aggregate allocas whose every use is a `getelementptr 0, k` with
+/// constant `k`, whose result is only loaded from and stored to, into one
+/// alloca per element used.
+fn split_aggregates(blocks: &mut [BasicBlock], struct_types: &HashMap<String, Type>) {
+    let instrs: Vec<&Instruction> = blocks.iter().flat_map(|block| &block.instrs).collect();
+    // Alloca name -> (GEP destination, element index, element type).
+    let mut splits: HashMap<String, Vec<(String, i64, Type)>> = HashMap::new();
+    for instr in &instrs {
+        let Instruction::Alloca { dst, ty, .. } = instr else {
+            continue;
+        };
+        let Some(name) = dst.local_name() else {
+            continue;
+        };
+        let ty = match ty {
+            Type::NamedStruct(name) => match struct_types.get(name) {
+                Some(body) => body,
+                None => continue,
+            },
+            ty => ty,
+        };
+        let len = match ty {
+            Type::Array { len, .. } => *len,
+            Type::Struct { fields, .. } => fields.len(),
+            _ => continue,
+        };
+        if len > MAX_SROA_ELEMENTS {
+            continue;
+        }
+        if let Some(elements) = element_accesses(name, &instrs) {
+            splits.insert(name.to_string(), elements);
+        }
+    }
+    if splits.is_empty() {
+        return;
+    }
+
+    // Each GEP destination becomes the address of its element's alloca.
+    let mut renamed: HashMap<String, Value> = HashMap::new();
+    for BasicBlock { instrs, .. } in blocks.iter_mut() {
+        let mut out = vec![];
+        for instr in instrs.drain(..) {
+            match &instr {
+                Instruction::Alloca { dst, align, .. }
+                    if let Some(elements) = dst.local_name().and_then(|n| splits.get(n)) =>
+                {
+                    let base = dst.local_name().unwrap_or_default();
+                    let mut made: Vec<i64> = vec![];
+                    for (gep, index, ty) in elements {
+                        let element = Value::Local {
+                            name: format!("{base}.{index}"),
+                            ty: Type::Ptr {
+                                pointee: Box::new(ty.clone()),
+                                addr_space: dst.ty().addr_space().unwrap_or(0),
+                            },
+                        };
+                        renamed.insert(gep.clone(), element.clone());
+                        if made.contains(index) {
+                            continue;
+                        }
+                        made.push(*index);
+                        out.push(Instruction::Alloca {
+                            dst: element,
+                            ty: ty.clone(),
+                            align: *align,
+                        });
+                    }
+                }
+                Instruction::GetElementPtr { dst, .. }
+                    if dst.local_name().is_some_and(|n| renamed.contains_key(n)) => {}
+                _ => out.push(instr),
+            }
+        }
+        *instrs = out;
+    }
+    for BasicBlock { instrs, .. } in blocks.iter_mut() {
+        for instr in instrs.iter_mut() {
+            for value in instr.values_mut() {
+                if let Some(new) = value.local_name().and_then(|n| renamed.get(n)) {
+                    *value = new.clone();
+                }
+            }
+        }
+    }
+}
+
+/// The `getelementptr 0, k` accesses of alloca `name`, if those are its
+/// only uses and their results are only loaded from and stored to.
+fn element_accesses(name: &str, instrs: &[&Instruction]) -> Option<Vec<(String, i64, Type)>> {
+    let is_slot = |value: &Value| value.local_name() == Some(name);
+    let mut elements = vec![];
+    for instr in instrs {
+        match instr {
+            Instruction::Alloca { .. } => {}
+            Instruction::GetElementPtr {
+                dst, base, indices, ..
+            } if is_slot(base) => {
+                let [first, index] = indices.as_slice() else {
+                    return None;
+                };
+                if first.as_signed() != Some(0) {
+                    return None;
+                }
+                let index = index.as_signed()?;
+                let ty = dst.ty().pointee()?.clone();
+                let gep = dst.local_name()?;
+                let uses: Vec<&Instruction> = instrs
+                    .iter()
+                    .copied()
+                    .filter(|i| !std::ptr::eq(*i, *instr))
+                    .collect();
+                if !is_scalar(&ty) || !promotable(gep, &ty, &uses) {
+                    return None;
+                }
+                elements.push((gep.to_string(), index, ty));
+            }
+            other if other.values().into_iter().any(is_slot) => return None,
+            _ => {}
+        }
+    }
+    Some(elements)
+}
+
+/// Drop the phis in `inserted` that no instruction other than a dead phi
+/// uses.
+fn remove_dead_phis(blocks: &mut [BasicBlock], inserted: &HashSet<String>) {
+    let is_inserted = |instr: &Instruction| match instr {
+        Instruction::Phi { dst, .. } => dst.local_name().is_some_and(|n| inserted.contains(n)),
+        _ => false,
+    };
+    let mut live: HashSet<String> = HashSet::new();
+    let mut work: Vec<&Instruction> = vec![];
+    for instr in blocks.iter().flat_map(|block| &block.instrs) {
+        if !is_inserted(instr) {
+            work.push(instr);
+        }
+    }
+    let phis: HashMap<&str, &Instruction> = blocks
+        .iter()
+        .flat_map(|block| &block.instrs)
+        .filter(|instr| is_inserted(instr))
+        .filter_map(|instr| Some((instr.values()[0].local_name()?, instr)))
+        .collect();
+    while let Some(instr) = work.pop() {
+        let skip = usize::from(matches!(instr, Instruction::Phi { .. }));
+        for value in instr.values().into_iter().skip(skip) {
+            let Some(name) = value.local_name() else {
+                continue;
+            };
+            if live.insert(name.to_string())
+                && let Some(phi) = phis.get(name)
+            {
+                work.push(phi);
+            }
+        }
+    }
+    for BasicBlock { instrs, .. } in blocks.iter_mut() {
+        instrs.retain(|instr| match instr {
+            Instruction::Phi { dst, .. } if is_inserted(instr) => {
+                dst.local_name().is_some_and(|n| live.contains(n))
+            }
+            _ => true,
+        });
+    }
+}
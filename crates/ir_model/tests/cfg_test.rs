@@ -0,0 +1,149 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir_model::cfg::Cfg;
+use llvm_parser::parse_llvm_ir_from_str;
+
+fn cfg(ll: &str) -> Cfg {
+    let module = parse_llvm_ir_from_str(ll).unwrap();
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).unwrap();
+    Cfg::new(&blocks)
+}
+
+fn labels(cfg: &Cfg, blocks: &[usize]) -> Vec<String> {
+    blocks.iter().map(|&b| cfg.label(b).to_string()).collect()
+}
+
+const DIAMOND: &str = r#"
+define i32 @pick(i1 %c, i32 %a, i32 %b) {
+entry:
+  br i1 %c, label %left, label %right
+
+left:
+  br label %join
+
+right:
+  br label %join
+
+join:
+  %v = phi i32 [ %a, %left ], [ %b, %right ]
+  ret i32 %v
+
+dead:
+  br label %join
+}
+"#;
+
+#[test]
+fn edges_follow_the_terminators() {
+    let cfg = cfg(DIAMOND);
+    let join = cfg.index("join").unwrap();
+
+    assert_eq!(labels(&cfg, cfg.successors(0)), vec!["left", "right"]);
+    assert_eq!(
+        labels(&cfg, cfg.predecessors(join)),
+        vec!["left", "right", "dead"]
+    );
+    assert!(cfg.successors(join).is_empty());
+    assert_eq!(
+        labels(&cfg, &cfg.reverse_post_order()),
+        vec!["entry", "right", "left", "join"]
+    );
+}
+
+#[test]
+fn dominators_of_a_diamond() {
+    let cfg = cfg(DIAMOND);
+    let doms = cfg.dominators();
+    let [entry, left, right, join, dead] = [0, 1, 2, 3, 4];
+
+    assert_eq!(doms.idom(entry), None);
+    assert_eq!(doms.idom(left), Some(entry));
+    assert_eq!(doms.idom(join), Some(entry));
+    assert!(doms.dominates(entry, join));
+    assert!(!doms.dominates(left, join));
+    assert!(!doms.contains(dead));
+    assert_eq!(doms.children(entry), vec![right, left, join]);
+
+    let frontiers = doms.frontiers();
+    assert_eq!(frontiers[left], vec![join]);
+    assert_eq!(frontiers[right], vec![join]);
+    assert!(frontiers[entry].is_empty());
+}
+
+#[test]
+fn post_dominators_of_a_diamond() {
+    let cfg = cfg(DIAMOND);
+    let post = cfg.post_dominators();
+    let [entry, left, right, join, dead] = [0, 1, 2, 3, 4];
+
+    assert_eq!(post.idom(join), None);
+    assert_eq!(post.idom(entry), Some(join));
+    assert_eq!(post.idom(left), Some(join));
+    assert_eq!(post.idom(dead), Some(join));
+    assert!(post.dominates(join, right));
+    assert!(!post.dominates(left, entry));
+
+    // The branch in `entry` decides whether `left` runs.
+    assert_eq!(post.frontiers()[left], vec![entry]);
+}
+
+#[test]
+fn nested_loops_are_found_outer_first() {
+    let cfg = cfg(r#"
+define void @nest(i32 %n) {
+entry:
+  br label %outer
+
+outer:
+  %i = phi i32 [ 0, %entry ], [ %i1, %outer_latch ]
+  br label %inner
+
+inner:
+  %j = phi i32 [ 0, %outer ], [ %j1, %inner ]
+  %j1 = add i32 %j, 1
+  %jdone = icmp eq i32 %j1, %n
+  br i1 %jdone, label %outer_latch, label %inner
+
+outer_latch:
+  %i1 = add i32 %i, 1
+  %idone = icmp eq i32 %i1, %n
+  br i1 %idone, label %exit, label %outer
+
+exit:
+  ret void
+}
+"#);
+    let loops = cfg.loops();
+
+    assert_eq!(loops.len(), 2);
+    assert_eq!(cfg.label(loops[0].header), "outer");
+    assert_eq!(labels(&cfg, &loops[0].latches), vec!["outer_latch"]);
+    assert_eq!(
+        labels(&cfg, &loops[0].blocks),
+        vec!["outer", "inner", "outer_latch"]
+    );
+    assert_eq!(cfg.label(loops[1].header), "inner");
+    assert_eq!(labels(&cfg, &loops[1].blocks), vec!["inner"]);
+    assert!(loops[0].contains(loops[1].header));
+    assert!(!loops[1].contains(cfg.index("exit").unwrap()));
+}
+
+#[test]
+fn straight_line_code_has_no_loops() {
+    assert!(cfg(DIAMOND).loops().is_empty());
+}
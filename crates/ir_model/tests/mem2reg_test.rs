@@ -0,0 +1,179 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir_model::mem2reg::promote_allocas;
+use ir_model::{BasicBlock, Instruction};
+use llvm_parser::parse_llvm_ir_from_str;
+
+fn lower(ll: &str) -> Vec<BasicBlock> {
+    let module = parse_llvm_ir_from_str(ll).unwrap();
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).unwrap();
+    promote_allocas(&blocks, &llvm_parser::lower_struct_types(&module.types))
+}
+
+fn instrs(blocks: &[BasicBlock]) -> Vec<&Instruction> {
+    blocks.iter().flat_map(|block| &block.instrs).collect()
+}
+
+/// Names of the allocas left in memory.
+fn allocas(blocks: &[BasicBlock]) -> Vec<String> {
+    instrs(blocks)
+        .into_iter()
+        .filter_map(|i| match i {
+            Instruction::Alloca { dst, .. } => dst.name().map(String::from),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `(block, value)` incoming pairs of the phi defining `name`.
+fn incoming(blocks: &[BasicBlock], name: &str) -> Vec<(String, String)> {
+    instrs(blocks)
+        .into_iter()
+        .find_map(|i| match i {
+            Instruction::Phi { dst, incoming, .. } if dst.name() == Some(name) => Some(
+                incoming
+                    .iter()
+                    .map(|(block, value)| {
+                        let value = match value.name() {
+                            Some(name) => name.to_string(),
+                            None => value.as_signed().unwrap_or_default().to_string(),
+                        };
+                        (block.clone(), value)
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn loop_counters_become_phis() {
+    let blocks = lower(include_str!("../../ptx_backend/tests/inputs/fib.ll"));
+
+    assert!(allocas(&blocks).is_empty());
+    assert!(
+        !instrs(&blocks)
+            .iter()
+            .any(|i| matches!(i, Instruction::Load { .. }))
+    );
+    assert_eq!(
+        incoming(&blocks, "i.loop"),
+        vec![("entry".into(), "0".into()), ("body".into(), "next".into())]
+    );
+}
+
+#[test]
+fn stores_on_both_arms_meet_in_a_phi() {
+    let blocks = lower(
+        r#"
+define i32 @pick(i1 %c, i32 %a, i32 %b) {
+entry:
+  %x = alloca i32, align 4
+  br i1 %c, label %left, label %right
+
+left:
+  store i32 %a, i32* %x, align 4
+  br label %join
+
+right:
+  store i32 %b, i32* %x, align 4
+  br label %join
+
+join:
+  %v = load i32, i32* %x, align 4
+  ret i32 %v
+}
+"#,
+    );
+
+    assert!(allocas(&blocks).is_empty());
+    assert_eq!(
+        incoming(&blocks, "x.join"),
+        vec![("left".into(), "a".into()), ("right".into(), "b".into())]
+    );
+    let join = &blocks.iter().find(|b| b.name == "join").unwrap().instrs;
+    assert!(matches!(
+        join.last(),
+        Some(Instruction::Ret { value: Some(v), .. }) if v.name() == Some("x.join")
+    ));
+}
+
+#[test]
+fn stores_in_one_block_need_no_phi() {
+    let blocks = lower(
+        r#"
+define i32 @straight(i32 %a) {
+entry:
+  %x = alloca i32, align 4
+  store i32 %a, i32* %x, align 4
+  br label %next
+
+next:
+  %v = load i32, i32* %x, align 4
+  ret i32 %v
+}
+"#,
+    );
+
+    assert!(allocas(&blocks).is_empty());
+    assert!(
+        !instrs(&blocks)
+            .iter()
+            .any(|i| matches!(i, Instruction::Phi { .. }))
+    );
+}
+
+#[test]
+fn constant_element_accesses_split_an_array() {
+    let blocks = lower(include_str!("../../ptx_backend/tests/inputs/frame.ll"));
+
+    // `%tmp` escapes into a call and stays; `%buf` is split and promoted.
+    assert_eq!(allocas(&blocks), vec!["tmp".to_string()]);
+    assert!(
+        !instrs(&blocks)
+            .iter()
+            .any(|i| matches!(i, Instruction::GetElementPtr { .. }))
+    );
+}
+
+#[test]
+fn named_structs_split_by_their_field_count() {
+    let blocks = lower(
+        r#"
+%pair = type { i32, float }
+%wide = type { i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32 }
+
+define float @k(i32 %a, float %b) {
+entry:
+  %p = alloca %pair, align 4
+  %w = alloca %wide, align 4
+  %pa = getelementptr %pair, %pair* %p, i32 0, i32 0
+  %pb = getelementptr %pair, %pair* %p, i32 0, i32 1
+  store i32 %a, i32* %pa, align 4
+  store float %b, float* %pb, align 4
+  %wa = getelementptr %wide, %wide* %w, i32 0, i32 1
+  store i32 %a, i32* %wa, align 4
+  %x = load float, float* %pb, align 4
+  ret float %x
+}
+"#,
+    );
+
+    assert_eq!(allocas(&blocks), vec!["w".to_string()]);
+}
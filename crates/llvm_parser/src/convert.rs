@@ -15,227 +15,648 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use ir_model::Instruction;
-use llvm_ir::instruction::Instruction as LlvmInst;
+use ir_model::{
+    AtomicOp, BarrierKind, Callee, CallingConv, GlobalVar, Initializer, Instruction,
+    MemoryOrdering, MemoryScope, SpecialRegister, Type, Value,
+};
+use llvm_ir::constant::{Constant, Float};
+use llvm_ir::function::{CallingConvention, FunctionAttribute, ParameterAttribute};
+use llvm_ir::instruction::{Instruction as LlvmInst, SynchronizationScope};
+use llvm_ir::types::{FPType, Types};
+use llvm_ir::{Name, Operand};
 
-pub fn lower(function: &str, instr: &LlvmInst) -> Instruction {
+pub fn lower(instr: &LlvmInst, types: &Types) -> Instruction {
+    lower_with_globals(instr, types, &[])
+}
+
+/// [`lower`], with references to `globals` typed in their address space.
+pub fn lower_with_globals(instr: &LlvmInst, types: &Types, globals: &[GlobalVar]) -> Instruction {
     use LlvmInst::*;
+    let op = |o: &Operand| lower_operand(o, types, globals);
+    let dest = |name: &Name| Value::Local {
+        name: name_to_string(name),
+        ty: lower_type(&types.type_of(instr)),
+    };
     match instr {
         FMul(f) => Instruction::FMul {
-            function: function.to_string(),
-            dst: f.dest.to_string(),
-            lhs: f.operand0.to_string(),
-            rhs: f.operand1.to_string(),
+            dst: dest(&f.dest),
+            lhs: op(&f.operand0),
+            rhs: op(&f.operand1),
         },
         FAdd(f) => Instruction::FAdd {
-            function: function.to_string(),
-            dst: f.dest.to_string(),
-            lhs: f.operand0.to_string(),
-            rhs: f.operand1.to_string(),
+            dst: dest(&f.dest),
+            lhs: op(&f.operand0),
+            rhs: op(&f.operand1),
         },
         Load(l) => Instruction::Load {
-            function: function.to_string(),
-            dst: l.dest.to_string(),
-            src: l.address.to_string(),
+            dst: dest(&l.dest),
+            src: op(&l.address),
         },
         Store(s) => Instruction::Store {
-            function: function.to_string(),
-            dst: s.address.to_string(),
-            value: s.value.to_string(),
+            dst: op(&s.address),
+            value: op(&s.value),
         },
-        Alloca(a) => Instruction::Alloca {
-            function: function.to_string(),
-            dst: a.dest.to_string(),
-            ty: format!("{:?}", a.allocated_type),
-            align: a.alignment,
+        // `alloca T, i32 n` with a constant count is an array of `n` T;
+        // a dynamic count has no fixed frame slot.
+        Alloca(a) => match op(&a.num_elements) {
+            Value::ConstInt { value, .. } => Instruction::Alloca {
+                dst: dest(&a.dest),
+                ty: match value {
+                    1 => lower_type(&a.allocated_type),
+                    len => Type::Array {
+                        elem: Box::new(lower_type(&a.allocated_type)),
+                        len: len as usize,
+                    },
+                },
+                align: a.alignment,
+            },
+            _ => Instruction::Unhandled {
+                text: format!("{:?}", instr),
+            },
         },
         ICmp(cmp) => Instruction::ICmp {
-            function: function.to_string(),
-            dst: cmp.dest.to_string(),
+            dst: dest(&cmp.dest),
             op: format!("{:?}", cmp.predicate),
-            lhs: cmp.operand0.to_string(),
-            rhs: cmp.operand1.to_string(),
+            lhs: op(&cmp.operand0),
+            rhs: op(&cmp.operand1),
         },
         FCmp(cmp) => Instruction::FCmp {
-            function: function.to_string(),
-            dst: cmp.dest.to_string(),
+            dst: dest(&cmp.dest),
             op: format!("{:?}", cmp.predicate),
-            lhs: cmp.operand0.to_string(),
-            rhs: cmp.operand1.to_string(),
+            lhs: op(&cmp.operand0),
+            rhs: op(&cmp.operand1),
         },
         Add(add) => Instruction::Add {
-            function: function.to_string(),
-            dst: add.dest.to_string(),
-            lhs: add.operand0.to_string(),
-            rhs: add.operand1.to_string(),
+            dst: dest(&add.dest),
+            lhs: op(&add.operand0),
+            rhs: op(&add.operand1),
         },
         Sub(s) => Instruction::Sub {
-            function: function.to_string(),
-            dst: s.dest.to_string(),
-            lhs: s.operand0.to_string(),
-            rhs: s.operand1.to_string(),
+            dst: dest(&s.dest),
+            lhs: op(&s.operand0),
+            rhs: op(&s.operand1),
+        },
+        And(b) => Instruction::And {
+            dst: dest(&b.dest),
+            lhs: op(&b.operand0),
+            rhs: op(&b.operand1),
+        },
+        Or(b) => Instruction::Or {
+            dst: dest(&b.dest),
+            lhs: op(&b.operand0),
+            rhs: op(&b.operand1),
+        },
+        Xor(b) => Instruction::Xor {
+            dst: dest(&b.dest),
+            lhs: op(&b.operand0),
+            rhs: op(&b.operand1),
+        },
+        Shl(b) => Instruction::Shl {
+            dst: dest(&b.dest),
+            lhs: op(&b.operand0),
+            rhs: op(&b.operand1),
+        },
+        LShr(b) => Instruction::LShr {
+            dst: dest(&b.dest),
+            lhs: op(&b.operand0),
+            rhs: op(&b.operand1),
+        },
+        AShr(b) => Instruction::AShr {
+            dst: dest(&b.dest),
+            lhs: op(&b.operand0),
+            rhs: op(&b.operand1),
         },
         FSub(s) => Instruction::FSub {
-            function: function.to_string(),
-            dst: s.dest.to_string(),
-            lhs: s.operand0.to_string(),
-            rhs: s.operand1.to_string(),
+            dst: dest(&s.dest),
+            lhs: op(&s.operand0),
+            rhs: op(&s.operand1),
         },
         Mul(m) => Instruction::Mul {
-            function: function.to_string(),
-            dst: m.dest.to_string(),
-            lhs: m.operand0.to_string(),
-            rhs: m.operand1.to_string(),
+            dst: dest(&m.dest),
+            lhs: op(&m.operand0),
+            rhs: op(&m.operand1),
         },
         UDiv(d) => Instruction::UDiv {
-            function: function.to_string(),
-            dst: d.dest.to_string(),
-            lhs: d.operand0.to_string(),
-            rhs: d.operand1.to_string(),
+            dst: dest(&d.dest),
+            lhs: op(&d.operand0),
+            rhs: op(&d.operand1),
         },
         SDiv(d) => Instruction::SDiv {
-            function: function.to_string(),
-            dst: d.dest.to_string(),
-            lhs: d.operand0.to_string(),
-            rhs: d.operand1.to_string(),
+            dst: dest(&d.dest),
+            lhs: op(&d.operand0),
+            rhs: op(&d.operand1),
         },
         URem(r) => Instruction::URem {
-            function: function.to_string(),
-            dst: r.dest.to_string(),
-            lhs: r.operand0.to_string(),
-            rhs: r.operand1.to_string(),
+            dst: dest(&r.dest),
+            lhs: op(&r.operand0),
+            rhs: op(&r.operand1),
         },
         SRem(r) => Instruction::SRem {
-            function: function.to_string(),
-            dst: r.dest.to_string(),
-            lhs: r.operand0.to_string(),
-            rhs: r.operand1.to_string(),
+            dst: dest(&r.dest),
+            lhs: op(&r.operand0),
+            rhs: op(&r.operand1),
         },
         FDiv(d) => Instruction::FDiv {
-            function: function.to_string(),
-            dst: d.dest.to_string(),
-            lhs: d.operand0.to_string(),
-            rhs: d.operand1.to_string(),
+            dst: dest(&d.dest),
+            lhs: op(&d.operand0),
+            rhs: op(&d.operand1),
         },
         FRem(r) => Instruction::FRem {
-            function: function.to_string(),
-            dst: r.dest.to_string(),
-            lhs: r.operand0.to_string(),
-            rhs: r.operand1.to_string(),
+            dst: dest(&r.dest),
+            lhs: op(&r.operand0),
+            rhs: op(&r.operand1),
         },
         GetElementPtr(gep) => Instruction::GetElementPtr {
-            function: function.to_string(),
-            dst: gep.dest.to_string(),
-            base: gep.address.to_string(),
-            index: gep
-                .indices
-                .iter()
-                .map(|i| i.to_string())
-                .collect::<Vec<_>>()
-                .join(", "),
+            dst: dest(&gep.dest),
+            base: op(&gep.address),
+            source_ty: lower_type(&gep.source_element_type),
+            indices: gep.indices.iter().map(op).collect(),
         },
         Phi(p) => Instruction::Phi {
-            function: function.to_string(),
-            dst: p.dest.to_string(),
+            dst: dest(&p.dest),
             incoming: p
                 .incoming_values
                 .iter()
-                .map(|(val, label)| {
-                    (
-                        match label {
-                            llvm_ir::Name::Name(s) => s.as_str().to_string(),
-                            llvm_ir::Name::Number(n) => format!("{}", n),
-                        },
-                        val.to_string(),
-                    )
-                })
+                .map(|(val, label)| (name_to_string(label), op(val)))
                 .collect(),
         },
         Select(sel) => Instruction::Select {
-            function: function.to_string(),
-            dst: sel.dest.to_string(),
-            cond: sel.condition.to_string(),
-            val_true: sel.true_value.to_string(),
-            val_false: sel.false_value.to_string(),
+            dst: dest(&sel.dest),
+            cond: op(&sel.condition),
+            val_true: op(&sel.true_value),
+            val_false: op(&sel.false_value),
         },
         BitCast(bc) => Instruction::Bitcast {
-            function: function.to_string(),
-            dst: bc.dest.to_string(),
-            src: bc.operand.to_string(),
+            dst: dest(&bc.dest),
+            src: op(&bc.operand),
         },
         ZExt(z) => Instruction::ZExt {
-            function: function.to_string(),
-            dst: z.dest.to_string(),
-            src: z.operand.to_string(),
+            dst: dest(&z.dest),
+            src: op(&z.operand),
         },
         Trunc(t) => Instruction::Trunc {
-            function: function.to_string(),
-            dst: t.dest.to_string(),
-            src: t.operand.to_string(),
+            dst: dest(&t.dest),
+            src: op(&t.operand),
+        },
+        SExt(c) => Instruction::SExt {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        FPToSI(c) => Instruction::FPToSI {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        FPToUI(c) => Instruction::FPToUI {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        SIToFP(c) => Instruction::SIToFP {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        UIToFP(c) => Instruction::UIToFP {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        FPExt(c) => Instruction::FPExt {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        FPTrunc(c) => Instruction::FPTrunc {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        PtrToInt(c) => Instruction::PtrToInt {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        IntToPtr(c) => Instruction::IntToPtr {
+            dst: dest(&c.dest),
+            src: op(&c.operand),
+        },
+        AddrSpaceCast(a) => Instruction::AddrSpaceCast {
+            dst: dest(&a.dest),
+            src: op(&a.operand),
         },
         Call(c) => {
             let target = match &c.function {
-                either::Either::Right(llvm_ir::Operand::ConstantOperand(const_ref)) => {
-                    match const_ref.as_ref() {
-                        llvm_ir::constant::Constant::GlobalReference { name, .. } => match name {
-                            llvm_ir::Name::Name(s) => s.to_string(),
-                            llvm_ir::Name::Number(n) => format!("{}", n),
-                        },
-                        _ => "unknown_fn".to_string(),
-                    }
+                either::Either::Right(callee) => match op(callee) {
+                    Value::Global { name, .. } => Callee::Direct(name),
+                    pointer => Callee::Indirect(pointer),
+                },
+                either::Either::Left(_) => {
+                    return Instruction::Unhandled {
+                        text: format!("{:?}", instr),
+                    };
                 }
-                _ => "unknown_fn".to_string(),
             };
 
-            let args = c
-                .arguments
-                .iter()
-                .map(|a| extract_operand_name(&a.0))
-                .collect();
+            let args: Vec<Value> = c.arguments.iter().map(|a| op(&a.0)).collect();
+            let byval = c.arguments.iter().map(|a| byval_type(&a.1)).collect();
+            let ret = c.dest.as_ref().map(dest);
+
+            if let Callee::Direct(name) = &target
+                && let Some(instr) = lower_intrinsic(name, &args, ret.as_ref())
+            {
+                return instr;
+            }
 
             Instruction::Call {
-                function: function.to_string(),
                 callee: target,
                 args,
-                ret: c.dest.clone().map(|n| n.to_string()),
+                byval,
+                ret,
             }
         }
+        AtomicRMW(a) => Instruction::AtomicRmw {
+            dst: Some(dest(&a.dest)),
+            op: lower_rmw_op(&a.operation),
+            ptr: op(&a.address),
+            value: op(&a.value),
+            ordering: lower_ordering(&a.atomicity.mem_ordering),
+            scope: lower_scope(&a.atomicity.synch_scope),
+        },
+        CmpXchg(c) => Instruction::CmpXchg {
+            dst: dest(&c.dest),
+            ptr: op(&c.address),
+            expected: op(&c.expected),
+            new: op(&c.replacement),
+            ordering: lower_ordering(&c.atomicity.mem_ordering),
+            scope: lower_scope(&c.atomicity.synch_scope),
+        },
+        ExtractValue(e) => Instruction::ExtractValue {
+            dst: dest(&e.dest),
+            aggregate: op(&e.aggregate),
+            indices: e.indices.clone(),
+        },
+        // The C API behind llvm-ir reads the ordering of a fence as if it
+        // were an atomicrmw and returns garbage, so every fence is taken as
+        // the strongest one until `recover_atomic_orderings` reads the real
+        // one from the IR text.
+        Fence(f) => Instruction::Fence {
+            ordering: MemoryOrdering::SeqCst,
+            scope: lower_scope(&f.atomicity.synch_scope),
+        },
         _ => Instruction::Unhandled {
-            function: function.to_string(),
             text: format!("{:?}", instr),
         },
     }
 }
 
-fn extract_operand_name(op: &llvm_ir::Operand) -> String {
+/// Recognise the NVVM intrinsics that have a dedicated IR instruction.
+fn lower_intrinsic(callee: &str, args: &[Value], ret: Option<&Value>) -> Option<Instruction> {
+    let barrier = |kind, aligned, id: Value, count: Option<&Value>| Instruction::Barrier {
+        kind,
+        aligned,
+        id,
+        count: count.cloned(),
+    };
+    let block_zero = || Value::ConstInt {
+        value: 0,
+        ty: Type::Int(32),
+    };
+
+    if let Some(name) = callee.strip_prefix("llvm.nvvm.read.ptx.sreg.") {
+        return Some(Instruction::ReadSpecialReg {
+            dst: ret?.clone(),
+            reg: SpecialRegister::from_name(name)?,
+        });
+    }
+
+    let instr = match callee {
+        "llvm.nvvm.barrier0" => barrier(BarrierKind::Sync, true, block_zero(), None),
+        "llvm.nvvm.barrier.n" | "llvm.nvvm.barrier.cta.sync.aligned.all" => {
+            barrier(BarrierKind::Sync, true, args.first()?.clone(), None)
+        }
+        "llvm.nvvm.barrier" | "llvm.nvvm.barrier.cta.sync.aligned.count" => {
+            barrier(BarrierKind::Sync, true, args.first()?.clone(), args.get(1))
+        }
+        "llvm.nvvm.barrier.sync" | "llvm.nvvm.barrier.cta.sync.all" => {
+            barrier(BarrierKind::Sync, false, args.first()?.clone(), None)
+        }
+        "llvm.nvvm.barrier.sync.cnt" | "llvm.nvvm.barrier.cta.sync.count" => {
+            barrier(BarrierKind::Sync, false, args.first()?.clone(), args.get(1))
+        }
+        "llvm.nvvm.barrier.cta.arrive.aligned.count" => barrier(
+            BarrierKind::Arrive,
+            true,
+            args.first()?.clone(),
+            args.get(1),
+        ),
+        "llvm.nvvm.barrier.cta.arrive.count" => barrier(
+            BarrierKind::Arrive,
+            false,
+            args.first()?.clone(),
+            args.get(1),
+        ),
+        "llvm.nvvm.membar.cta" => Instruction::MemBar {
+            scope: MemoryScope::Block,
+        },
+        "llvm.nvvm.membar.gl" => Instruction::MemBar {
+            scope: MemoryScope::Device,
+        },
+        "llvm.nvvm.membar.sys" => Instruction::MemBar {
+            scope: MemoryScope::System,
+        },
+        _ => return None,
+    };
+    Some(instr)
+}
+
+fn lower_rmw_op(op: &llvm_ir::instruction::RMWBinOp) -> AtomicOp {
+    use llvm_ir::instruction::RMWBinOp as B;
     match op {
-        llvm_ir::Operand::LocalOperand { name, .. } => name.to_string(),
-        llvm_ir::Operand::ConstantOperand(c) => format!("{}", c),
-        _ => format!("{:?}", op), // Puedes refinar esto según tus casos esperados
+        B::Xchg => AtomicOp::Xchg,
+        B::Add => AtomicOp::Add,
+        B::Sub => AtomicOp::Sub,
+        B::And => AtomicOp::And,
+        B::Nand => AtomicOp::Nand,
+        B::Or => AtomicOp::Or,
+        B::Xor => AtomicOp::Xor,
+        B::Max => AtomicOp::Max,
+        B::Min => AtomicOp::Min,
+        B::UMax => AtomicOp::UMax,
+        B::UMin => AtomicOp::UMin,
+        B::FAdd => AtomicOp::FAdd,
+        B::FSub => AtomicOp::FSub,
+    }
+}
+
+fn lower_ordering(ordering: &llvm_ir::instruction::MemoryOrdering) -> MemoryOrdering {
+    use llvm_ir::instruction::MemoryOrdering as O;
+    match ordering {
+        O::Unordered | O::Monotonic | O::NotAtomic => MemoryOrdering::Relaxed,
+        O::Acquire => MemoryOrdering::Acquire,
+        O::Release => MemoryOrdering::Release,
+        O::AcquireRelease => MemoryOrdering::AcqRel,
+        O::SequentiallyConsistent => MemoryOrdering::SeqCst,
+    }
+}
+
+/// LLVM only distinguishes `singlethread` from the default system scope
+/// here; target-specific scopes such as `syncscope("device")` are not
+/// exposed by the parser and read as system scope, which is the
+/// conservative choice, until `recover_atomic_orderings` reads them from
+/// the IR text.
+fn lower_scope(scope: &SynchronizationScope) -> MemoryScope {
+    match scope {
+        SynchronizationScope::SingleThread => MemoryScope::Block,
+        SynchronizationScope::System => MemoryScope::System,
     }
 }
 
+/// LLVM spelling of a function attribute; `None` for attributes the
+/// parser does not know.
+pub fn attribute_to_string(attribute: &FunctionAttribute) -> Option<String> {
+    match attribute {
+        FunctionAttribute::StringAttribute { kind, value } if value.is_empty() => {
+            Some(format!("\"{kind}\""))
+        }
+        FunctionAttribute::StringAttribute { kind, value } => {
+            Some(format!("\"{kind}\"=\"{value}\""))
+        }
+        FunctionAttribute::UnknownAttribute => None,
+        other => Some(format!("{other:?}").to_lowercase()),
+    }
+}
+
+pub fn lower_calling_convention(cc: CallingConvention) -> CallingConv {
+    match cc {
+        CallingConvention::C => CallingConv::C,
+        CallingConvention::PTX_Kernel => CallingConv::PtxKernel,
+        CallingConvention::PTX_Device => CallingConv::PtxDevice,
+        _ => CallingConv::Other,
+    }
+}
+
+/// Strip the `%` sigil LLVM puts in front of local and block names.
+pub fn name_to_string(name: &Name) -> String {
+    match name {
+        Name::Name(s) => s.to_string(),
+        Name::Number(n) => format!("{}", n),
+    }
+}
+
+/// Map an LLVM type onto the IR model type.
+pub fn lower_type(ty: &llvm_ir::Type) -> Type {
+    use llvm_ir::Type as T;
+    match ty {
+        T::VoidType => Type::Void,
+        T::IntegerType { bits } => Type::Int(*bits),
+        T::FPType(FPType::Half) => Type::Half,
+        T::FPType(FPType::Single) => Type::Float,
+        T::FPType(FPType::Double) => Type::Double,
+        T::PointerType {
+            pointee_type,
+            addr_space,
+        } => Type::Ptr {
+            pointee: Box::new(lower_type(pointee_type)),
+            addr_space: *addr_space,
+        },
+        T::VectorType {
+            element_type,
+            num_elements,
+            ..
+        } => Type::Vector {
+            elem: Box::new(lower_type(element_type)),
+            len: *num_elements,
+        },
+        T::ArrayType {
+            element_type,
+            num_elements,
+        } => Type::Array {
+            elem: Box::new(lower_type(element_type)),
+            len: *num_elements,
+        },
+        T::StructType {
+            element_types,
+            is_packed,
+        } => Type::Struct {
+            fields: element_types.iter().map(|t| lower_type(t)).collect(),
+            packed: *is_packed,
+        },
+        T::NamedStructType { name } => Type::NamedStruct(name.clone()),
+        T::FuncType {
+            result_type,
+            param_types,
+            is_var_arg,
+        } => Type::Function {
+            ret: Box::new(lower_type(result_type)),
+            params: param_types.iter().map(|t| lower_type(t)).collect(),
+            var_arg: *is_var_arg,
+        },
+        T::LabelType => Type::Label,
+        other => Type::Other(format!("{}", other)),
+    }
+}
+
+/// Map an LLVM operand onto a typed IR value.
+pub fn lower_operand(op: &Operand, types: &Types, globals: &[GlobalVar]) -> Value {
+    match op {
+        Operand::LocalOperand { name, ty } => Value::Local {
+            name: name_to_string(name),
+            ty: lower_type(ty),
+        },
+        Operand::ConstantOperand(c) => lower_constant(c, types, globals),
+        Operand::MetadataOperand => Value::Unhandled {
+            text: "metadata".to_string(),
+            ty: Type::Other("metadata".to_string()),
+        },
+    }
+}
+
+/// Map an LLVM constant onto a typed IR value.
+///
+/// `llvm-ir` types every reference to a global as a generic pointer, so
+/// the address space of variables is taken from `globals` instead.
+pub fn lower_constant(c: &Constant, types: &Types, globals: &[GlobalVar]) -> Value {
+    let ty = lower_type(&types.type_of(c));
+    match c {
+        Constant::Int { value, .. } => Value::ConstInt { value: *value, ty },
+        Constant::Float(Float::Single(f)) => Value::ConstFloat {
+            value: *f as f64,
+            ty,
+        },
+        Constant::Float(Float::Double(f)) => Value::ConstFloat { value: *f, ty },
+        Constant::Null(_) => Value::Null { ty },
+        Constant::Undef(_) | Constant::Poison(_) => Value::Undef { ty },
+        Constant::GlobalReference { name, .. } => {
+            let name = name_to_string(name);
+            let ty = match globals.iter().find(|g| g.name == name) {
+                Some(global) => global.pointer_type(),
+                None => ty,
+            };
+            Value::Global { name, ty }
+        }
+        // `getelementptr (@g, 0, 0)` decays an array global to a
+        // pointer to its first element, as for string literals.
+        Constant::GetElementPtr(gep)
+            if gep
+                .indices
+                .iter()
+                .all(|i| matches!(i.as_ref(), Constant::Int { value: 0, .. })) =>
+        {
+            match lower_constant(&gep.address, types, globals) {
+                Value::Global {
+                    name,
+                    ty: Type::Ptr { addr_space, .. },
+                } => Value::Global {
+                    name,
+                    ty: match ty {
+                        Type::Ptr { pointee, .. } => Type::Ptr {
+                            pointee,
+                            addr_space,
+                        },
+                        other => other,
+                    },
+                },
+                _ => Value::Unhandled {
+                    text: format!("{}", c),
+                    ty,
+                },
+            }
+        }
+        // Casts of a global only change the type of the reference.
+        Constant::AddrSpaceCast(llvm_ir::constant::AddrSpaceCast { operand, .. })
+        | Constant::BitCast(llvm_ir::constant::BitCast { operand, .. }) => {
+            match lower_constant(operand, types, globals) {
+                Value::Global { name, .. } => Value::Global { name, ty },
+                _ => Value::Unhandled {
+                    text: format!("{}", c),
+                    ty,
+                },
+            }
+        }
+        other => Value::Unhandled {
+            text: format!("{}", other),
+            ty,
+        },
+    }
+}
+
+/// Initial value of a global variable; `None` when it is `undef`.
+pub fn lower_initializer(
+    c: &Constant,
+    types: &Types,
+    globals: &[GlobalVar],
+) -> Option<Initializer> {
+    let ty = || lower_type(&types.type_of(c));
+    let elements = |elements: &[llvm_ir::ConstantRef]| {
+        elements
+            .iter()
+            .map(|e| {
+                lower_initializer(e, types, globals)
+                    .unwrap_or_else(|| Initializer::Zero(lower_type(&types.type_of(e.as_ref()))))
+            })
+            .collect()
+    };
+    Some(match c {
+        Constant::Undef(_) | Constant::Poison(_) => return None,
+        Constant::AggregateZero(_) => Initializer::Zero(ty()),
+        Constant::Array { elements: e, .. } | Constant::Vector(e) => {
+            Initializer::Aggregate(ty(), elements(e))
+        }
+        Constant::Struct { values, .. } => Initializer::Aggregate(ty(), elements(values)),
+        scalar => Initializer::Scalar(lower_constant(scalar, types, globals)),
+    })
+}
+
+/// Bytes of an `i8` array constant, such as a string literal.
+pub fn constant_bytes(constant: &Constant) -> Option<Vec<u8>> {
+    match constant {
+        Constant::Array { elements, .. } => elements
+            .iter()
+            .map(|e| match e.as_ref() {
+                Constant::Int { bits: 8, value } => Some(*value as u8),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Pointee type of a `byval` parameter or argument.
+pub fn byval_type(attributes: &[ParameterAttribute]) -> Option<Type> {
+    attributes.iter().find_map(|attribute| match attribute {
+        ParameterAttribute::ByVal(ty) => Some(lower_type(ty)),
+        _ => None,
+    })
+}
+
 use llvm_ir::Terminator;
 
-pub fn lower_terminator(func: &str, term: &Terminator) -> Instruction {
+pub fn lower_terminator(term: &Terminator, types: &Types, globals: &[GlobalVar]) -> Instruction {
     match term {
-        Terminator::Ret(_) => Instruction::Ret {
-            function: func.to_string(),
+        Terminator::Ret(ret) => Instruction::Ret {
+            value: ret
+                .return_operand
+                .as_ref()
+                .map(|op| lower_operand(op, types, globals)),
         },
         Terminator::CondBr(br) => Instruction::CondBr {
-            function: func.to_string(),
-            cond: format!("{}", br.condition),
-            then_target: br.true_dest.to_string(),
-            else_target: br.false_dest.to_string(),
+            cond: lower_operand(&br.condition, types, globals),
+            then_target: name_to_string(&br.true_dest),
+            else_target: name_to_string(&br.false_dest),
         },
         Terminator::Br(br) => Instruction::Br {
-            function: func.to_string(),
             cond: None,
-            target_true: br.dest.to_string(),
+            target_true: name_to_string(&br.dest),
             target_false: None,
         },
+        Terminator::Switch(sw) => Instruction::Switch {
+            cond: lower_operand(&sw.operand, types, globals),
+            default: name_to_string(&sw.default_dest),
+            cases: sw
+                .dests
+                .iter()
+                .map(|(value, dest)| {
+                    let value = Operand::ConstantOperand(value.clone());
+                    (lower_operand(&value, types, globals), name_to_string(dest))
+                })
+                .collect(),
+        },
+        Terminator::Unreachable(_) => Instruction::Unreachable,
         _ => Instruction::Unhandled {
-            function: func.to_string(),
             text: format!("{:?}", term),
         },
     }
@@ -18,29 +18,293 @@
 pub mod convert;
 pub mod parse_module;
 
-use anyhow::Result;
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use ir_model::{
+    BasicBlock, GlobalVar, Initializer, Instruction, MemoryOrdering, MemoryScope, Signature, Type,
+    Value,
+};
+use llvm_ir::function::{CallingConvention, Parameter};
+use llvm_ir::module::Linkage;
+use llvm_ir::types::{NamedStructDef, TypeRef, Types};
 use llvm_ir::{Function, Module};
-use ir_model::Instruction;
 
 pub fn parse_llvm_ir_from_str(ir: &str) -> Result<Module> {
     let module = Module::from_ir_str(ir).map_err(anyhow::Error::msg)?;
     Ok(module)
 }
 
-pub fn lower(func: &Function) -> Result<Vec<(String, Vec<Instruction>)>> {
+/// Lower the formal parameters of a function into typed locals.
+pub fn lower_params(func: &Function) -> Vec<Value> {
+    func.parameters
+        .iter()
+        .map(|p| Value::Local {
+            name: convert::name_to_string(&p.name),
+            ty: convert::lower_type(&p.ty),
+        })
+        .collect()
+}
+
+/// Lower the parameters, with their `byval` types, and the return type
+/// of a function or declaration.
+pub fn lower_signature(params: &[Parameter], ret: &TypeRef) -> Signature {
+    Signature {
+        params: params
+            .iter()
+            .map(|p| Value::Local {
+                name: convert::name_to_string(&p.name),
+                ty: convert::lower_type(&p.ty),
+            })
+            .collect(),
+        byval: params
+            .iter()
+            .map(|p| convert::byval_type(&p.attributes))
+            .collect(),
+        ret: convert::lower_type(ret),
+    }
+}
+
+/// Lower the bodies of the module's named struct types. Opaque structs
+/// have no layout and are left out.
+pub fn lower_struct_types(types: &Types) -> HashMap<String, Type> {
+    types
+        .all_struct_names()
+        .filter_map(|name| match types.named_struct_def(name)? {
+            NamedStructDef::Defined(ty) => Some((name.clone(), convert::lower_type(ty))),
+            NamedStructDef::Opaque => None,
+        })
+        .collect()
+}
+
+/// Names of the functions marked as kernels in `!nvvm.annotations`.
+///
+/// `llvm-ir` does not expose named metadata, so the annotations are read
+/// from the IR text: every node listed in `!nvvm.annotations` of the form
+/// `!{<ty> @name, !"kernel", i32 1}` marks `name` as a kernel.
+pub fn nvvm_kernels(ir: &str) -> Vec<String> {
+    let node_ids = |list: &str| -> Vec<String> {
+        list.split([',', '{', '}'])
+            .map(str::trim)
+            .filter(|id| id.starts_with('!') && id.len() > 1)
+            .map(String::from)
+            .collect()
+    };
+
+    let Some(annotated) = ir
+        .lines()
+        .map(str::trim)
+        .find_map(|l| l.strip_prefix("!nvvm.annotations"))
+        .map(node_ids)
+    else {
+        return vec![];
+    };
+
+    let mut kernels = vec![];
+    for line in ir.lines().map(str::trim) {
+        let Some((id, node)) = line.split_once(" = ") else {
+            continue;
+        };
+        if !annotated.iter().any(|a| a == id) || !node.contains("!\"kernel\", i32 1") {
+            continue;
+        }
+        let Some(start) = node.find('@') else {
+            continue;
+        };
+        let name: String = node[start + 1..]
+            .trim_start_matches('"')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$'))
+            .collect();
+        if !name.is_empty() && !kernels.contains(&name) {
+            kernels.push(name);
+        }
+    }
+    kernels
+}
+
+/// Ordering and scope of every `fence`, `atomicrmw` and `cmpxchg`, in the
+/// order they appear in the IR text.
+///
+/// The C API behind `llvm-ir` cannot read the ordering of a fence and only
+/// knows the `singlethread` and system scopes, so both are read from the
+/// IR text instead. For `cmpxchg` the ordering is the success one.
+pub fn atomic_orderings(ir: &str) -> Vec<(MemoryOrdering, MemoryScope)> {
+    let mut orderings = vec![];
+    for line in ir.lines().map(str::trim) {
+        let instr = match line.split_once(" = ") {
+            Some((dst, instr)) if dst.starts_with('%') => instr,
+            _ => line,
+        };
+        if !["fence ", "atomicrmw ", "cmpxchg "]
+            .iter()
+            .any(|opcode| instr.starts_with(opcode))
+        {
+            continue;
+        }
+        let scope = match instr
+            .split_once("syncscope(\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+        {
+            Some(("singlethread" | "block", _)) => MemoryScope::Block,
+            Some(("device" | "cluster", _)) => MemoryScope::Device,
+            _ => MemoryScope::System,
+        };
+        let ordering = instr
+            .split([' ', ','])
+            .find_map(|word| match word {
+                "unordered" | "monotonic" => Some(MemoryOrdering::Relaxed),
+                "acquire" => Some(MemoryOrdering::Acquire),
+                "release" => Some(MemoryOrdering::Release),
+                "acq_rel" => Some(MemoryOrdering::AcqRel),
+                "seq_cst" => Some(MemoryOrdering::SeqCst),
+                _ => None,
+            })
+            .unwrap_or(MemoryOrdering::SeqCst);
+        orderings.push((ordering, scope));
+    }
+    orderings
+}
+
+/// Replace the orderings and scopes of the atomic instructions of `module`
+/// with those read by [`atomic_orderings`] from `ir`, the text it was
+/// parsed from. Fails, leaving the module alone, if the two do not line
+/// up, e.g. when an atomic instruction is split over several lines.
+pub fn recover_atomic_orderings(module: &mut ir_model::Module, ir: &str) -> Result<()> {
+    let orderings = atomic_orderings(ir);
+    let mut atomics: Vec<(&mut MemoryOrdering, &mut MemoryScope)> = module
+        .functions
+        .iter_mut()
+        .flat_map(|f| f.blocks.iter_mut())
+        .flat_map(|b| b.instrs.iter_mut())
+        .filter_map(|instr| match instr {
+            Instruction::Fence { ordering, scope }
+            | Instruction::AtomicRmw {
+                ordering, scope, ..
+            }
+            | Instruction::CmpXchg {
+                ordering, scope, ..
+            } => Some((ordering, scope)),
+            _ => None,
+        })
+        .collect();
+    if atomics.len() != orderings.len() {
+        bail!(
+            "cannot recover atomic orderings: {} atomic instructions in the module but {} in the IR text",
+            atomics.len(),
+            orderings.len()
+        );
+    }
+    for ((ordering, scope), (text_ordering, text_scope)) in atomics.iter_mut().zip(orderings) {
+        **ordering = text_ordering;
+        **scope = text_scope;
+    }
+    Ok(())
+}
+
+pub fn lower(func: &Function, types: &Types) -> Result<Vec<BasicBlock>> {
+    lower_with_globals(func, types, &[])
+}
+
+/// [`lower`], with references to `globals` typed in their address space.
+pub fn lower_with_globals(
+    func: &Function,
+    types: &Types,
+    globals: &[GlobalVar],
+) -> Result<Vec<BasicBlock>> {
     let mut blocks = vec![];
     for block in &func.basic_blocks {
         let mut instrs = vec![];
 
         for instr in &block.instrs {
-            instrs.push(convert::lower(&func.name, instr));
+            instrs.push(convert::lower_with_globals(instr, types, globals));
         }
 
         // 👇 Esta parte es clave: también baja el terminator
-        instrs.push(convert::lower_terminator(&func.name, &block.term));
+        instrs.push(convert::lower_terminator(&block.term, types, globals));
 
-        blocks.push((block.name.to_string(), instrs));
+        blocks.push(BasicBlock::new(
+            convert::name_to_string(&block.name),
+            instrs,
+        ));
     }
     Ok(blocks)
 }
 
+/// Lower a whole module: its variables, then every function definition
+/// and declaration. Only functions with the `ptx_kernel` calling
+/// convention are marked as kernels here; callers that know of other
+/// kernels, such as those in `!nvvm.annotations`, mark them afterwards.
+pub fn lower_module(module: &Module) -> Result<ir_model::Module> {
+    let globals = lower_globals(module);
+    let mut functions = vec![];
+    for func in &module.functions {
+        functions.push(ir_model::Function {
+            name: func.name.clone(),
+            signature: lower_signature(&func.parameters, &func.return_type),
+            attributes: func
+                .function_attributes
+                .iter()
+                .filter_map(convert::attribute_to_string)
+                .collect(),
+            kernel: func.calling_convention == CallingConvention::PTX_Kernel,
+            calling_conv: convert::lower_calling_convention(func.calling_convention),
+            internal: matches!(func.linkage, Linkage::Private | Linkage::Internal),
+            blocks: lower_with_globals(func, &module.types, &globals)?,
+        });
+    }
+    for decl in &module.func_declarations {
+        functions.push(ir_model::Function {
+            name: decl.name.clone(),
+            signature: lower_signature(&decl.parameters, &decl.return_type),
+            attributes: vec![],
+            kernel: false,
+            calling_conv: convert::lower_calling_convention(decl.calling_convention),
+            internal: false,
+            blocks: vec![],
+        });
+    }
+    Ok(ir_model::Module {
+        functions,
+        globals,
+        data_layout: module.data_layout.layout_str.clone(),
+        struct_types: lower_struct_types(&module.types),
+    })
+}
+
+/// Lower the global variables of a module, with their initializers.
+///
+/// Initializers may refer to other globals, so the variables are lowered
+/// first and their initializers second.
+pub fn lower_globals(module: &Module) -> Vec<GlobalVar> {
+    let mut globals: Vec<GlobalVar> = module
+        .global_vars
+        .iter()
+        .map(|g| GlobalVar {
+            name: convert::name_to_string(&g.name),
+            ty: match g.ty.as_ref() {
+                llvm_ir::Type::PointerType { pointee_type, .. } => {
+                    convert::lower_type(pointee_type)
+                }
+                other => convert::lower_type(other),
+            },
+            addr_space: g.addr_space,
+            align: g.alignment,
+            constant: g.is_constant,
+            init: None,
+        })
+        .collect();
+    let inits: Vec<Option<Initializer>> = module
+        .global_vars
+        .iter()
+        .map(|g| {
+            g.initializer
+                .as_ref()
+                .and_then(|init| convert::lower_initializer(init, &module.types, &globals))
+        })
+        .collect();
+    for (global, init) in globals.iter_mut().zip(inits) {
+        global.init = init;
+    }
+    globals
+}
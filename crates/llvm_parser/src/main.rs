@@ -19,7 +19,6 @@ use ir_model::Instruction;
 use llvm_ir::Module;
 use llvm_parser::convert::lower;
 
-use serde_json;
 use std::env;
 use std::fs;
 
@@ -35,7 +34,7 @@ fn main() {
         for block in &func.basic_blocks {
             println!("  Basic block: {}", block.name);
             for instr in &block.instrs {
-                let lowered: Instruction = lower(&func.name, instr);
+                let lowered: Instruction = lower(instr, &module.types);
                 println!("{}", serde_json::to_string_pretty(&lowered).unwrap());
             }
         }
@@ -0,0 +1,27 @@
+define void @saxpy(float %a, float* %x, float* %y, float* %out, i32 %n) {
+entry:
+  %i = alloca i32, align 4
+  store i32 0, i32* %i, align 4
+  br label %loop
+
+loop:
+  %idx = load i32, i32* %i, align 4
+  %cmp = icmp slt i32 %idx, %n
+  br i1 %cmp, label %body, label %exit
+
+body:
+  %x_ptr = getelementptr float, float* %x, i32 %idx
+  %y_ptr = getelementptr float, float* %y, i32 %idx
+  %out_ptr = getelementptr float, float* %out, i32 %idx
+  %xval = load float, float* %x_ptr, align 4
+  %yval = load float, float* %y_ptr, align 4
+  %ax = fmul float %a, %xval
+  %res = fadd float %ax, %yval
+  store float %res, float* %out_ptr, align 4
+  %next = add i32 %idx, 1
+  store i32 %next, i32* %i, align 4
+  br label %loop
+
+exit:
+  ret void
+}
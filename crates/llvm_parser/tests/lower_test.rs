@@ -0,0 +1,240 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use insta::assert_debug_snapshot;
+use llvm_parser::parse_module::parse_module;
+
+#[test]
+fn test_saxpy_lowering() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("inputs")
+        .join("saxpy.ll");
+
+    let module = parse_module(path).expect("Failed to parse module");
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).expect("Failed to lower");
+    assert_debug_snapshot!("saxpy_lowering", blocks);
+}
+
+#[test]
+fn test_special_register_intrinsics() {
+    use ir_model::{Dim, Instruction, SpecialRegister};
+
+    let module = llvm_parser::parse_llvm_ir_from_str(
+        r#"
+declare i32 @llvm.nvvm.read.ptx.sreg.ctaid.y()
+declare i32 @llvm.nvvm.read.ptx.sreg.warpsize()
+
+define void @k() {
+entry:
+  %b = call i32 @llvm.nvvm.read.ptx.sreg.ctaid.y()
+  %w = call i32 @llvm.nvvm.read.ptx.sreg.warpsize()
+  ret void
+}
+"#,
+    )
+    .expect("Failed to parse module");
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).expect("Failed to lower");
+
+    let regs: Vec<SpecialRegister> = blocks[0]
+        .instrs
+        .iter()
+        .filter_map(|instr| match instr {
+            Instruction::ReadSpecialReg { reg, .. } => Some(*reg),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        regs,
+        vec![SpecialRegister::Ctaid(Dim::Y), SpecialRegister::WarpSize]
+    );
+    assert_eq!(
+        SpecialRegister::from_name("ntid.z"),
+        Some(SpecialRegister::Ntid(Dim::Z))
+    );
+    assert_eq!(SpecialRegister::from_name("tid.w"), None);
+}
+
+#[test]
+fn test_indirect_call_keeps_the_pointer() {
+    use ir_model::{Callee, Instruction, Value};
+
+    let module = llvm_parser::parse_llvm_ir_from_str(
+        r#"
+define void @k(void (i32)* %f) {
+entry:
+  call void %f(i32 1)
+  ret void
+}
+"#,
+    )
+    .expect("Failed to parse module");
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).expect("Failed to lower");
+
+    match &blocks[0].instrs[0] {
+        Instruction::Call {
+            callee: Callee::Indirect(Value::Local { name, .. }),
+            ..
+        } => assert_eq!(name, "f"),
+        other => panic!("expected an indirect call, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_global_references_keep_their_address_space() {
+    use ir_model::{Initializer, Instruction};
+
+    let module = llvm_parser::parse_llvm_ir_from_str(
+        r#"
+@tile = addrspace(3) global [4 x float] undef, align 4
+@one = addrspace(1) global i32 1, align 4
+
+define void @k() {
+entry:
+  %v = load i32, i32 addrspace(1)* @one, align 4
+  store float 0.0, float* getelementptr ([4 x float], [4 x float]* addrspacecast ([4 x float] addrspace(3)* @tile to [4 x float]*), i64 0, i64 0), align 4
+  ret void
+}
+"#,
+    )
+    .expect("Failed to parse module");
+    let globals = llvm_parser::lower_globals(&module);
+    assert_eq!(globals[0].init, None);
+    assert!(matches!(globals[1].init, Some(Initializer::Scalar(_))));
+
+    let blocks = llvm_parser::lower_with_globals(&module.functions[0], &module.types, &globals)
+        .expect("Failed to lower");
+    match &blocks[0].instrs[..2] {
+        [
+            Instruction::Load { src, .. },
+            Instruction::Store { dst, .. },
+        ] => {
+            assert_eq!(src.ty().addr_space(), Some(1));
+            assert_eq!(dst.ty().addr_space(), Some(0));
+        }
+        other => panic!("expected a load and a store, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_module_lowering_keeps_function_properties() {
+    use ir_model::{CallingConv, Type};
+
+    let module = llvm_parser::parse_llvm_ir_from_str(
+        r#"
+declare i32 @ext(i32)
+
+define ptx_kernel void @k(i32* %out) #0 {
+entry:
+  %v = call i32 @ext(i32 1)
+  store i32 %v, i32* %out, align 4
+  ret void
+}
+
+define internal float @helper(float %x) {
+entry:
+  ret float %x
+}
+
+attributes #0 = { nounwind "target-cpu"="sm_70" }
+"#,
+    )
+    .expect("Failed to parse module");
+    let module = llvm_parser::lower_module(&module).expect("Failed to lower");
+
+    let k = module.function("k").unwrap();
+    assert!(k.kernel);
+    assert_eq!(k.calling_conv, CallingConv::PtxKernel);
+    assert!(!k.internal);
+    assert_eq!(k.signature.params.len(), 1);
+    assert_eq!(k.attributes, vec!["nounwind", "\"target-cpu\"=\"sm_70\""]);
+    assert_eq!(k.blocks[0].name, "entry");
+    assert_eq!(k.instructions().count(), 3);
+
+    let helper = module.function("helper").unwrap();
+    assert!(!helper.kernel);
+    assert!(helper.internal);
+    assert_eq!(helper.signature.ret, Type::Float);
+
+    let ext = module.function("ext").unwrap();
+    assert!(ext.is_declaration());
+    assert_eq!(
+        module
+            .definitions()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["k", "helper"]
+    );
+}
+
+#[test]
+fn test_atomic_orderings_are_recovered_from_the_text() {
+    use ir_model::{Instruction, MemoryOrdering, MemoryScope};
+
+    // The commented-out fence is not an instruction and must be skipped.
+    let ir = r#"
+define void @k(i32* %p) {
+entry:
+  ; fence seq_cst
+  fence syncscope("block") acquire
+  %old = atomicrmw add i32* %p, i32 1 syncscope("device") monotonic
+  ret void
+}
+"#;
+    let module = llvm_parser::parse_llvm_ir_from_str(ir).expect("Failed to parse module");
+    let mut module = llvm_parser::lower_module(&module).expect("Failed to lower");
+    llvm_parser::recover_atomic_orderings(&mut module, ir).expect("Failed to recover");
+
+    let instrs: Vec<_> = module.function("k").unwrap().instructions().collect();
+    assert!(matches!(
+        instrs[0],
+        Instruction::Fence {
+            ordering: MemoryOrdering::Acquire,
+            scope: MemoryScope::Block,
+        }
+    ));
+    assert!(matches!(
+        instrs[1],
+        Instruction::AtomicRmw {
+            ordering: MemoryOrdering::Relaxed,
+            scope: MemoryScope::Device,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_atomic_ordering_count_mismatch_is_an_error() {
+    // A fence split over two lines is not found in the text.
+    let ir = r#"
+define void @k() {
+entry:
+  fence
+    syncscope("block") acquire
+  ret void
+}
+"#;
+    let module = llvm_parser::parse_llvm_ir_from_str(ir).expect("Failed to parse module");
+    let mut module = llvm_parser::lower_module(&module).expect("Failed to lower");
+    let err = llvm_parser::recover_atomic_orderings(&mut module, ir).unwrap_err();
+
+    assert!(
+        err.to_string()
+            .contains("1 atomic instructions in the module but 0"),
+        "{err}"
+    );
+}
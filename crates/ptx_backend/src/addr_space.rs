@@ -0,0 +1,317 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+// Address space inference for pointer registers.
+
+use std::collections::HashMap;
+
+use ir_model::{BasicBlock, GlobalVar, Instruction, Type, Value};
+
+use crate::globals::variable_space;
+
+/// PTX state space a pointer refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSpace {
+    Generic,
+    Global,
+    Shared,
+    Const,
+    Local,
+    Param,
+}
+
+impl AddressSpace {
+    /// Map an NVPTX LLVM address space number.
+    pub fn from_llvm(addr_space: u32) -> Self {
+        match addr_space {
+            1 => AddressSpace::Global,
+            3 => AddressSpace::Shared,
+            4 => AddressSpace::Const,
+            5 => AddressSpace::Local,
+            101 => AddressSpace::Param,
+            _ => AddressSpace::Generic,
+        }
+    }
+
+    /// State space modifier for `ld`/`st`/`cvta`, `None` for generic.
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            AddressSpace::Generic => None,
+            AddressSpace::Global => Some("global"),
+            AddressSpace::Shared => Some("shared"),
+            AddressSpace::Const => Some("const"),
+            AddressSpace::Local => Some("local"),
+            AddressSpace::Param => Some("param"),
+        }
+    }
+}
+
+/// Specific address spaces proven for generic pointer registers.
+///
+/// A register listed here holds an address in that space rather than a
+/// generic address, so every instruction deriving it (parameter loads,
+/// `addrspacecast`) must produce the specific form.
+#[derive(Debug, Default)]
+pub struct AddressSpaces {
+    regs: HashMap<String, AddressSpace>,
+    /// State space of each module variable, by name.
+    variables: HashMap<String, AddressSpace>,
+}
+
+impl AddressSpaces {
+    /// Know the state space of the module's variables, so that loads and
+    /// stores can address them by symbol.
+    pub fn with_variables(mut self, globals: &[GlobalVar]) -> Self {
+        self.variables = globals
+            .iter()
+            .map(|g| (g.name.clone(), variable_space(g)))
+            .collect();
+        self
+    }
+
+    /// State space of the variable `ptr` refers to, if it is a global
+    /// variable rather than a function or a register.
+    pub fn variable(&self, ptr: &Value) -> Option<AddressSpace> {
+        match ptr {
+            Value::Global { name, .. } => self.variables.get(name).copied(),
+            _ => None,
+        }
+    }
+
+    /// Address space of the memory `ptr` points to.
+    pub fn of(&self, ptr: &Value) -> AddressSpace {
+        match ptr.ty().addr_space() {
+            Some(0) | None => ptr
+                .local_name()
+                .and_then(|name| self.regs.get(name))
+                .copied()
+                .unwrap_or(AddressSpace::Generic),
+            Some(n) => AddressSpace::from_llvm(n),
+        }
+    }
+}
+
+/// Infer the address space of generic pointers from their origin.
+///
+/// Generic pointers connected through `getelementptr`, `bitcast`, `select`
+/// and copies share one address space. It is specific when every origin in
+/// the group agrees on it (`kernel_params` point to global memory, allocas
+/// to local memory, `addrspacecast` to the space of its source) and no
+/// pointer of the group escapes, i.e. is used other than as an address or
+/// to derive another pointer of the group. Anything else stays generic.
+pub fn infer_address_spaces(blocks: &[BasicBlock], kernel_params: &[Value]) -> AddressSpaces {
+    let params: Vec<(Value, AddressSpace)> = kernel_params
+        .iter()
+        .map(|param| (param.clone(), AddressSpace::Global))
+        .collect();
+    infer_address_spaces_from(blocks, &params)
+}
+
+/// [`infer_address_spaces`], with the space each pointer parameter points
+/// to given explicitly.
+pub fn infer_address_spaces_from(
+    blocks: &[BasicBlock],
+    params: &[(Value, AddressSpace)],
+) -> AddressSpaces {
+    let mut groups = Groups::default();
+    // Origin of each group member, keyed by register name.
+    let mut origins: Vec<(String, AddressSpace)> = vec![];
+    let mut escaping: Vec<String> = vec![];
+
+    for (param, space) in params {
+        if let Some(name) = generic_ptr(param) {
+            origins.push((name.to_string(), *space));
+        }
+    }
+
+    for block in blocks {
+        for instr in &block.instrs {
+            let (dst, derived_from): (Option<&Value>, Vec<&Value>) = match instr {
+                Instruction::GetElementPtr { dst, base, .. } => (Some(dst), vec![base]),
+                Instruction::Bitcast { dst, src, .. } | Instruction::Copy { dst, src, .. } => {
+                    (Some(dst), vec![src])
+                }
+                Instruction::Select {
+                    dst,
+                    val_true,
+                    val_false,
+                    ..
+                } => (Some(dst), vec![val_true, val_false]),
+                Instruction::Phi { dst, incoming, .. } => {
+                    (Some(dst), incoming.iter().map(|(_, v)| v).collect())
+                }
+                _ => (None, vec![]),
+            };
+
+            if let Some(dst_name) = dst.and_then(generic_ptr) {
+                for src in derived_from {
+                    match generic_ptr(src) {
+                        Some(src_name) => groups.union(dst_name, src_name),
+                        None if matches!(src, Value::Undef { .. }) => {}
+                        None => origins.push((dst_name.to_string(), AddressSpace::Generic)),
+                    }
+                }
+            } else {
+                // Deriving a pointer that is not a generic register (or an
+                // integer, for `select` on pointers) lets the address escape.
+                escaping.extend(
+                    derived_from
+                        .into_iter()
+                        .filter_map(generic_ptr)
+                        .map(String::from),
+                );
+            }
+
+            match instr {
+                Instruction::Alloca { dst, .. } => {
+                    if let Some(name) = generic_ptr(dst) {
+                        origins.push((name.to_string(), AddressSpace::Local));
+                    }
+                }
+                Instruction::AddrSpaceCast { dst, src, .. } => {
+                    if let Some(name) = generic_ptr(dst) {
+                        let space = AddressSpaces::default().of(src);
+                        origins.push((name.to_string(), space));
+                    }
+                }
+                Instruction::Load { dst, .. } => {
+                    if let Some(name) = generic_ptr(dst) {
+                        origins.push((name.to_string(), AddressSpace::Generic));
+                    }
+                }
+                Instruction::Store { value, .. } => {
+                    escaping.extend(generic_ptr(value).map(String::from));
+                }
+                Instruction::AtomicRmw { dst, value, .. } => {
+                    escaping.extend(generic_ptr(value).map(String::from));
+                    if let Some(name) = dst.as_ref().and_then(generic_ptr) {
+                        origins.push((name.to_string(), AddressSpace::Generic));
+                    }
+                }
+                Instruction::CmpXchg { expected, new, .. } => {
+                    escaping.extend(
+                        [expected, new]
+                            .into_iter()
+                            .filter_map(generic_ptr)
+                            .map(String::from),
+                    );
+                }
+                Instruction::Call {
+                    args, byval, ret, ..
+                } => {
+                    // A `byval` argument is only read, to copy it.
+                    escaping.extend(
+                        args.iter()
+                            .enumerate()
+                            .filter(|(i, _)| byval.get(*i).is_none_or(Option::is_none))
+                            .filter_map(|(_, arg)| generic_ptr(arg))
+                            .map(String::from),
+                    );
+                    if let Some(name) = ret.as_ref().and_then(generic_ptr) {
+                        origins.push((name.to_string(), AddressSpace::Generic));
+                    }
+                }
+                Instruction::GetElementPtr { .. }
+                | Instruction::Bitcast { .. }
+                | Instruction::Copy { .. }
+                | Instruction::Select { .. }
+                | Instruction::Phi { .. } => {}
+                other => {
+                    // Any other use (compares, integer casts...) observes the
+                    // address itself; any other definition is unknown.
+                    for value in other.values() {
+                        if let Some(name) = generic_ptr(value) {
+                            escaping.push(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut group_space: HashMap<String, Option<AddressSpace>> = HashMap::new();
+    for (name, space) in &origins {
+        let root = groups.find(name);
+        let entry = group_space.entry(root).or_insert(Some(*space));
+        if *entry != Some(*space) {
+            *entry = Some(AddressSpace::Generic);
+        }
+    }
+    for name in &escaping {
+        group_space.insert(groups.find(name), Some(AddressSpace::Generic));
+    }
+
+    let mut regs = HashMap::new();
+    for name in groups.members() {
+        let root = groups.find(&name);
+        if let Some(Some(space)) = group_space.get(&root)
+            && *space != AddressSpace::Generic
+        {
+            regs.insert(name, *space);
+        }
+    }
+    AddressSpaces {
+        regs,
+        variables: HashMap::new(),
+    }
+}
+
+/// Register name of a local pointer into the generic address space.
+/// Function pointers are code addresses and have no state space.
+fn generic_ptr(value: &Value) -> Option<&str> {
+    match value {
+        Value::Local { name, ty }
+            if ty.addr_space() == Some(0)
+                && !matches!(ty.pointee(), Some(Type::Function { .. })) =>
+        {
+            Some(name)
+        }
+        _ => None,
+    }
+}
+
+/// Union-find over register names.
+#[derive(Default)]
+struct Groups {
+    parent: HashMap<String, String>,
+}
+
+impl Groups {
+    fn find(&mut self, name: &str) -> String {
+        let parent = self
+            .parent
+            .entry(name.to_string())
+            .or_insert_with(|| name.to_string())
+            .clone();
+        if parent == name {
+            return parent;
+        }
+        let root = self.find(&parent);
+        self.parent.insert(name.to_string(), root.clone());
+        root
+    }
+
+    fn union(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent.insert(a, b);
+        }
+    }
+
+    fn members(&self) -> Vec<String> {
+        self.parent.keys().cloned().collect()
+    }
+}
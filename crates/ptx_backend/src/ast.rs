@@ -0,0 +1,180 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+// Typed representation of PTX code, built by the lowering and turned into
+// text by `printer`.
+
+use ir_model::{Type, Value};
+
+use crate::addr_space::AddressSpace;
+use crate::error::CodegenError;
+use crate::globals::global_address_reg;
+use crate::ptx_type::PTXType;
+use crate::utils::clean_operand;
+
+/// Instruction operand.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// Register, without the `%` sigil.
+    Reg(String),
+    Imm(i64),
+    F32(f32),
+    F64(f64),
+    /// Variable, function or label name.
+    Symbol(String),
+    /// Memory reference `[base+offset]`.
+    Addr(Box<Operand>, i64),
+    /// Parenthesized list, as in call arguments.
+    List(Vec<Operand>),
+}
+
+impl Operand {
+    pub fn reg(name: &str) -> Self {
+        Operand::Reg(clean_operand(name))
+    }
+
+    pub fn symbol(name: &str) -> Self {
+        Operand::Symbol(clean_operand(name))
+    }
+
+    /// `[operand]`.
+    pub fn addr(self) -> Self {
+        Operand::Addr(Box::new(self), 0)
+    }
+
+    /// Registers for locals and immediates for constants. The addresses
+    /// of globals are in registers too, taken on entry, since only `mov`
+    /// and memory operands take a symbol. Values the parser could not
+    /// lower have no operand form and are an error.
+    pub fn from_value(value: &Value) -> Result<Self, CodegenError> {
+        let operand = match value {
+            Value::Local { name, .. } => Operand::reg(name),
+            Value::Global { name, ty } => Operand::Reg(global_address_reg(name, ty)),
+            Value::ConstInt { ty, value } if *ty == Type::Int(1) => {
+                Operand::Imm((value & 1) as i64)
+            }
+            Value::ConstInt { .. } => Operand::Imm(value.as_signed().unwrap_or_default()),
+            Value::ConstFloat { value, ty } => match ty {
+                Type::Double => Operand::F64(*value),
+                _ => Operand::F32(*value as f32),
+            },
+            Value::Null { .. } | Value::Undef { .. } => Operand::Imm(0),
+            Value::Unhandled { text, .. } => {
+                return Err(CodegenError::new(format!("unhandled value: {}", text)));
+            }
+        };
+        Ok(operand)
+    }
+}
+
+/// Guard predicate of an instruction (`@%p` or `@!%p`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    pub reg: String,
+    pub negated: bool,
+}
+
+/// A single PTX instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub pred: Option<Predicate>,
+    pub opcode: String,
+    /// Dotted suffixes of the opcode, in order (`["global", "f32"]`).
+    pub modifiers: Vec<String>,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    pub fn new(opcode: &str, modifiers: &[&str], operands: Vec<Operand>) -> Self {
+        Instruction {
+            pred: None,
+            opcode: opcode.to_string(),
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            operands,
+        }
+    }
+
+    /// Guard the instruction with predicate register `reg`.
+    pub fn guarded(mut self, reg: &Operand) -> Self {
+        if let Operand::Reg(reg) = reg {
+            self.pred = Some(Predicate {
+                reg: reg.clone(),
+                negated: false,
+            });
+        }
+        self
+    }
+}
+
+/// Variable declaration in a state space, at module scope, in a parameter
+/// list or inside a function body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarDecl {
+    pub space: AddressSpace,
+    pub align: Option<u64>,
+    /// Element type, without the dot (`b8`, `f32`...).
+    pub ty: String,
+    pub name: String,
+    /// Element count, for arrays.
+    pub len: Option<u64>,
+    /// Initial value, one operand per element; empty when uninitialized.
+    pub init: Vec<Operand>,
+}
+
+impl VarDecl {
+    pub fn param(ty: &str, name: &str) -> Self {
+        VarDecl {
+            space: AddressSpace::Param,
+            align: None,
+            ty: ty.to_string(),
+            name: name.to_string(),
+            len: None,
+            init: vec![],
+        }
+    }
+}
+
+/// `.callprototype` of indirect calls: the parameter types of a callee
+/// without its name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallPrototype {
+    /// Label the calls refer to.
+    pub name: String,
+    pub ret: Option<VarDecl>,
+    pub params: Vec<VarDecl>,
+}
+
+/// A line, or group of lines, of a function body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Label(String),
+    Instr(Instruction),
+    /// `.reg` declaration of registers sharing a type.
+    Regs(PTXType, Vec<String>),
+    Var(VarDecl),
+    Comment(String),
+    /// `label: .branchtargets a, b, ...;`, the table of a `brx.idx`.
+    BranchTargets(String, Vec<String>),
+    Prototype(CallPrototype),
+    /// `{ ... }` block, scoping the declarations it contains.
+    Scope(Vec<Statement>),
+}
+
+impl From<Instruction> for Statement {
+    fn from(instr: Instruction) -> Self {
+        Statement::Instr(instr)
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Atomic read-modify-write and compare-and-swap lowering to `atom`/`red`.
+
+use std::collections::HashSet;
+
+use ir_model::{
+    AtomicOp, BasicBlock, Instruction as IrInstruction, MemoryOrdering, MemoryScope, Type, Value,
+};
+
+use crate::addr_space::AddressSpace;
+use crate::ast::{Instruction, Operand};
+use crate::error::CodegenError;
+use crate::module::CodegenOptions;
+use crate::utils::clean_operand;
+
+/// Drop the result of `atomicrmw` instructions nobody reads, so they can be
+/// lowered to `red`. Only relaxed and release operations qualify, since
+/// `red` has no acquire semantics.
+pub fn drop_unused_results(blocks: &[BasicBlock]) -> Vec<BasicBlock> {
+    let mut used: HashSet<String> = HashSet::new();
+    for instr in blocks.iter().flat_map(|block| &block.instrs) {
+        let defined = match instr {
+            IrInstruction::AtomicRmw { dst, .. } => dst.as_ref(),
+            _ => None,
+        };
+        for value in instr.values() {
+            if Some(value) != defined
+                && let Some(name) = value.local_name()
+            {
+                used.insert(name.to_string());
+            }
+        }
+    }
+
+    blocks
+        .iter()
+        .map(|block| {
+            let instrs = block
+                .instrs
+                .iter()
+                .cloned()
+                .map(|mut instr| {
+                    if let IrInstruction::AtomicRmw { dst, ordering, .. } = &mut instr
+                        && matches!(ordering, MemoryOrdering::Relaxed | MemoryOrdering::Release)
+                        && dst
+                            .as_ref()
+                            .and_then(Value::local_name)
+                            .is_some_and(|name| !used.contains(name))
+                    {
+                        *dst = None;
+                    }
+                    instr
+                })
+                .collect();
+            BasicBlock::new(block.name.clone(), instrs)
+        })
+        .collect()
+}
+
+/// Registers holding the old value and the success flag of a `cmpxchg`,
+/// which `extractvalue` reads back.
+pub fn cas_scratch(dst: &Value) -> (String, String) {
+    let name = clean_operand(dst.local_name().unwrap_or("cas"));
+    (format!("{name}$old"), format!("{name}$ok"))
+}
+
+/// Register holding the negated operand of a subtraction, which PTX only
+/// has as an addition. `None` for constants, negated in place.
+pub fn neg_scratch(op: AtomicOp, value: &Value) -> Option<String> {
+    match op {
+        AtomicOp::Sub | AtomicOp::FSub => value
+            .local_name()
+            .map(|name| format!("{}$neg", clean_operand(name))),
+        _ => None,
+    }
+}
+
+/// Lower an `atomicrmw`, to `red` when its result was dropped.
+pub fn lower_atomic_rmw(
+    dst: Option<&Value>,
+    op: AtomicOp,
+    ptr: &Value,
+    value: &Value,
+    (ordering, scope): (MemoryOrdering, MemoryScope),
+    space: AddressSpace,
+    options: &CodegenOptions,
+) -> Result<Vec<Instruction>, CodegenError> {
+    let bits = bit_width(value.ty())?;
+    let float = value.ty().is_float();
+    let (name, ty) = match op {
+        AtomicOp::Xchg => ("exch", format!("b{bits}")),
+        AtomicOp::Add | AtomicOp::Sub => ("add", format!("u{bits}")),
+        AtomicOp::FAdd | AtomicOp::FSub if float => ("add", format!("f{bits}")),
+        AtomicOp::And => ("and", format!("b{bits}")),
+        AtomicOp::Or => ("or", format!("b{bits}")),
+        AtomicOp::Xor => ("xor", format!("b{bits}")),
+        AtomicOp::Max => ("max", format!("s{bits}")),
+        AtomicOp::Min => ("min", format!("s{bits}")),
+        AtomicOp::UMax => ("max", format!("u{bits}")),
+        AtomicOp::UMin => ("min", format!("u{bits}")),
+        _ => {
+            return Err(CodegenError::new(format!(
+                "unsupported atomicrmw operation: {:?}",
+                op
+            )));
+        }
+    };
+
+    let mut instrs = vec![];
+    let operand = match (neg_scratch(op, value), value) {
+        (Some(scratch), _) => {
+            let neg_ty = if float {
+                format!("f{bits}")
+            } else {
+                format!("s{bits}")
+            };
+            instrs.push(Instruction::new(
+                "neg",
+                &[&neg_ty],
+                vec![Operand::Reg(scratch.clone()), Operand::from_value(value)?],
+            ));
+            Operand::Reg(scratch)
+        }
+        (None, Value::ConstInt { .. }) if op == AtomicOp::Sub => {
+            Operand::Imm(value.as_signed().unwrap_or_default().wrapping_neg())
+        }
+        (None, Value::ConstFloat { value: v, ty }) if op == AtomicOp::FSub => {
+            Operand::from_value(&Value::ConstFloat {
+                value: -v,
+                ty: ty.clone(),
+            })?
+        }
+        _ => Operand::from_value(value)?,
+    };
+    let addr = Operand::from_value(ptr)?.addr();
+
+    let (opcode, operands) = match dst {
+        Some(dst) => ("atom", vec![Operand::from_value(dst)?, addr, operand]),
+        None => ("red", vec![addr, operand]),
+    };
+    let ordered = Ordered::new(ordering, scope, options);
+    let modifiers = ordered.modifiers(space, name, &ty);
+    let modifiers: Vec<&str> = modifiers.iter().map(String::as_str).collect();
+
+    instrs.extend(ordered.before());
+    instrs.push(Instruction::new(opcode, &modifiers, operands));
+    instrs.extend(ordered.after());
+    Ok(instrs)
+}
+
+/// Lower a `cmpxchg` to `atom.cas` into its scratch registers, then set
+/// the success flag by comparing the old value with the expected one.
+pub fn lower_cmpxchg(
+    dst: &Value,
+    ptr: &Value,
+    expected: &Value,
+    new: &Value,
+    (ordering, scope): (MemoryOrdering, MemoryScope),
+    space: AddressSpace,
+    options: &CodegenOptions,
+) -> Result<Vec<Instruction>, CodegenError> {
+    let ty = format!("b{}", bit_width(expected.ty())?);
+    let (old, ok) = cas_scratch(dst);
+    let ordered = Ordered::new(ordering, scope, options);
+    let modifiers = ordered.modifiers(space, "cas", &ty);
+    let modifiers: Vec<&str> = modifiers.iter().map(String::as_str).collect();
+
+    let mut instrs = ordered.before();
+    instrs.push(Instruction::new(
+        "atom",
+        &modifiers,
+        vec![
+            Operand::Reg(old.clone()),
+            Operand::from_value(ptr)?.addr(),
+            Operand::from_value(expected)?,
+            Operand::from_value(new)?,
+        ],
+    ));
+    instrs.extend(ordered.after());
+    instrs.push(Instruction::new(
+        "setp",
+        &["eq", &ty],
+        vec![
+            Operand::Reg(ok),
+            Operand::Reg(old),
+            Operand::from_value(expected)?,
+        ],
+    ));
+    Ok(instrs)
+}
+
+/// Width of an atomic operand. PTX atomics work on 32 and 64 bits.
+fn bit_width(ty: &Type) -> Result<u32, CodegenError> {
+    let bits = match ty {
+        Type::Int(bits) => *bits,
+        Type::Float => 32,
+        Type::Double | Type::Ptr { .. } => 64,
+        _ => 0,
+    };
+    if bits == 32 || bits == 64 {
+        Ok(bits)
+    } else {
+        Err(CodegenError::new(format!(
+            "unsupported atomic type: {:?}",
+            ty
+        )))
+    }
+}
+
+/// Memory ordering of an atomic, as `.sem` and `.scope` qualifiers on
+/// sm_70 and later, or as `membar` around the operation before that.
+struct Ordered {
+    ordering: MemoryOrdering,
+    scope: MemoryScope,
+    qualified: bool,
+}
+
+impl Ordered {
+    fn new(ordering: MemoryOrdering, scope: MemoryScope, options: &CodegenOptions) -> Self {
+        Ordered {
+            ordering,
+            scope,
+            qualified: options.sm_version().is_some_and(|sm| sm >= 70),
+        }
+    }
+
+    fn modifiers(&self, space: AddressSpace, op: &str, ty: &str) -> Vec<String> {
+        let mut modifiers = vec![];
+        if self.qualified {
+            let sem = match self.ordering {
+                MemoryOrdering::Relaxed => "relaxed",
+                MemoryOrdering::Acquire => "acquire",
+                MemoryOrdering::Release => "release",
+                // The sequentially consistent part is the leading fence.
+                MemoryOrdering::AcqRel | MemoryOrdering::SeqCst => "acq_rel",
+            };
+            modifiers.push(sem.to_string());
+            modifiers.push(scope_name(self.scope).to_string());
+        }
+        modifiers.extend(space.as_str().map(String::from));
+        modifiers.push(op.to_string());
+        modifiers.push(ty.to_string());
+        modifiers
+    }
+
+    fn releases(&self) -> bool {
+        !matches!(
+            self.ordering,
+            MemoryOrdering::Relaxed | MemoryOrdering::Acquire
+        )
+    }
+
+    fn acquires(&self) -> bool {
+        !matches!(
+            self.ordering,
+            MemoryOrdering::Relaxed | MemoryOrdering::Release
+        )
+    }
+
+    fn before(&self) -> Vec<Instruction> {
+        if self.qualified {
+            if self.ordering == MemoryOrdering::SeqCst {
+                return vec![Instruction::new(
+                    "fence",
+                    &["sc", scope_name(self.scope)],
+                    vec![],
+                )];
+            }
+            return vec![];
+        }
+        if self.releases() {
+            vec![self.membar()]
+        } else {
+            vec![]
+        }
+    }
+
+    fn after(&self) -> Vec<Instruction> {
+        if !self.qualified && self.acquires() {
+            vec![self.membar()]
+        } else {
+            vec![]
+        }
+    }
+
+    fn membar(&self) -> Instruction {
+        Instruction::new("membar", &[membar_level(self.scope)], vec![])
+    }
+}
+
+/// PTX name of a memory scope.
+pub fn scope_name(scope: MemoryScope) -> &'static str {
+    match scope {
+        MemoryScope::Block => "cta",
+        MemoryScope::Device => "gpu",
+        MemoryScope::System => "sys",
+    }
+}
+
+/// `membar` level of a memory scope.
+pub fn membar_level(scope: MemoryScope) -> &'static str {
+    match scope {
+        MemoryScope::Block => "cta",
+        MemoryScope::Device => "gl",
+        MemoryScope::System => "sys",
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Call sequences following the PTX calling convention.
+
+use ir_model::cfg::Cfg;
+use ir_model::{BasicBlock, Callee, Type, Value};
+
+use crate::addr_space::{AddressSpace, AddressSpaces};
+use crate::ast::{CallPrototype, Instruction, Operand, Statement, VarDecl};
+use crate::data_layout::DataLayout;
+use crate::error::CodegenError;
+use crate::ptx_type::PTXType;
+use crate::utils::clean_operand;
+
+/// Which blocks of a kernel every thread of a warp executes together, so
+/// that a direct call there can be `call.uni`: those post-dominating the
+/// entry block that are reached only through blocks with a single
+/// successor. Any conditional branch is taken as possibly divergent.
+pub fn uniform_blocks(blocks: &[BasicBlock]) -> Vec<bool> {
+    let cfg = Cfg::new(blocks);
+    if cfg.is_empty() {
+        return vec![];
+    }
+    let post_dominators = cfg.post_dominators();
+    (0..cfg.len())
+        .map(|block| {
+            if !post_dominators.dominates(block, 0) {
+                return false;
+            }
+            let mut seen = vec![false; cfg.len()];
+            let mut stack = cfg.predecessors(block).to_vec();
+            while let Some(pred) = stack.pop() {
+                if std::mem::replace(&mut seen[pred], true) {
+                    continue;
+                }
+                if cfg.successors(pred).len() > 1 {
+                    return false;
+                }
+                stack.extend_from_slice(cfg.predecessors(pred));
+            }
+            true
+        })
+        .collect()
+}
+
+/// `.param .align N .b8 name[size]` variable holding an aggregate passed
+/// `byval`.
+pub fn aggregate_param(name: &str, ty: &Type, layout: &DataLayout) -> VarDecl {
+    VarDecl {
+        space: AddressSpace::Param,
+        align: Some(layout.align_of(ty)),
+        ty: "b8".to_string(),
+        name: name.to_string(),
+        len: Some(layout.size_of(ty)),
+        init: vec![],
+    }
+}
+
+/// Bit-size type of the `.param` variable passing a scalar, the same size
+/// as the callee's declaration (`b8` for `i1` and `i8`, `b32` for `float`).
+fn param_bits(ty: &Type) -> Option<&'static str> {
+    Some(match PTXType::param_type(ty)? {
+        "u8" => "b8",
+        "u16" | "b16" => "b16",
+        "u32" | "f32" => "b32",
+        _ => "b64",
+    })
+}
+
+/// Type of the `st.param`/`ld.param` moving a scalar: floats keep their
+/// type, everything else moves as bits.
+fn move_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Float => Some("f32"),
+        Type::Double => Some("f64"),
+        _ => param_bits(ty),
+    }
+}
+
+/// Bytes copied at once into an aggregate argument, and the scratch
+/// register type holding them.
+pub fn copy_unit(ty: &Type, layout: &DataLayout) -> (u64, PTXType) {
+    let size = layout.size_of(ty);
+    let mut unit = layout.align_of(ty).clamp(1, 8);
+    while !size.is_multiple_of(unit) {
+        unit /= 2;
+    }
+    let scratch = match unit {
+        8 => PTXType::S64,
+        4 => PTXType::S32,
+        _ => PTXType::S16,
+    };
+    (unit, scratch)
+}
+
+/// Scratch register of an `i1` argument, stored as a byte.
+fn arg_scratch(arg: &Value) -> Option<String> {
+    let name = arg.local_name()?;
+    (*arg.ty() == Type::Int(1)).then(|| format!("{}$arg", clean_operand(name)))
+}
+
+/// Scratch register of an `i1` result, loaded as a byte.
+fn result_scratch(ret: &Value) -> Option<String> {
+    let name = ret.local_name()?;
+    (*ret.ty() == Type::Int(1)).then(|| format!("{}$retval", clean_operand(name)))
+}
+
+/// Scratch register copying the aggregate behind a `byval` pointer.
+pub fn byval_scratch(ptr: &Value) -> Option<String> {
+    ptr.name()
+        .map(|name| format!("{}$byval", clean_operand(name)))
+}
+
+/// Scratch registers a call needs, with their types.
+pub fn call_scratch(
+    args: &[Value],
+    byval: &[Option<Type>],
+    ret: Option<&Value>,
+    layout: &DataLayout,
+) -> Vec<(String, PTXType)> {
+    let mut scratch = vec![];
+    for (i, arg) in args.iter().enumerate() {
+        match byval.get(i).and_then(Option::as_ref) {
+            Some(ty) => {
+                scratch.extend(byval_scratch(arg).map(|name| (name, copy_unit(ty, layout).1)))
+            }
+            None => scratch.extend(arg_scratch(arg).map(|name| (name, PTXType::S16))),
+        }
+    }
+    scratch.extend(
+        ret.and_then(result_scratch)
+            .map(|name| (name, PTXType::S16)),
+    );
+    scratch
+}
+
+/// `.param` variable passing argument `arg`.
+fn arg_decl(
+    name: &str,
+    arg: &Value,
+    byval: Option<&Type>,
+    layout: &DataLayout,
+) -> Result<VarDecl, CodegenError> {
+    match byval {
+        Some(ty) => Ok(aggregate_param(name, ty, layout)),
+        None => param_bits(arg.ty())
+            .map(|bits| VarDecl::param(bits, name))
+            .ok_or_else(|| CodegenError::new(format!("unsupported argument type {:?}", arg.ty()))),
+    }
+}
+
+/// `.param` variable receiving the result `ret`.
+fn ret_decl(name: &str, ret: &Value) -> Result<VarDecl, CodegenError> {
+    param_bits(ret.ty())
+        .map(|bits| VarDecl::param(bits, name))
+        .ok_or_else(|| CodegenError::new(format!("unsupported return type {:?}", ret.ty())))
+}
+
+/// `.callprototype` describing an indirect call, named after its
+/// parameter types so that calls of the same shape share it.
+pub fn prototype(
+    args: &[Value],
+    byval: &[Option<Type>],
+    ret: Option<&Value>,
+    layout: &DataLayout,
+) -> Result<CallPrototype, CodegenError> {
+    let ret = ret.map(|ret| ret_decl("_", ret)).transpose()?;
+    let params = args
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| arg_decl("_", arg, byval.get(i).and_then(Option::as_ref), layout))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let shape = |decl: &VarDecl| match decl.len {
+        Some(len) => format!("a{}x{len}", decl.align.unwrap_or(1)),
+        None => decl.ty.clone(),
+    };
+    let name = std::iter::once(ret.as_ref().map_or("v".to_string(), shape))
+        .chain(params.iter().map(shape))
+        .collect::<Vec<_>>()
+        .join("_");
+    Ok(CallPrototype {
+        name: format!("$proto_{name}"),
+        ret,
+        params,
+    })
+}
+
+/// Lower a call into a `{ ... }` scope declaring one `.param` variable per
+/// argument, `param0` onwards, and `retval0` for the result.
+///
+/// Scalars travel in bit-size variables matching the callee's
+/// declarations, `i1` as a byte. Aggregates passed `byval` are copied from
+/// memory into an aligned byte array. A direct call in a `uniform` block
+/// (see [`uniform_blocks`]) uses `call.uni`; an indirect call goes through
+/// a function pointer that may differ between threads and names its
+/// [`prototype`].
+pub fn lower_call(
+    callee: &Callee,
+    args: &[Value],
+    byval: &[Option<Type>],
+    ret: Option<&Value>,
+    uniform: bool,
+    spaces: &AddressSpaces,
+    layout: &DataLayout,
+) -> Result<Vec<Statement>, CodegenError> {
+    let mut scope = vec![];
+    let mut params = vec![];
+
+    for (i, arg) in args.iter().enumerate() {
+        let name = format!("param{i}");
+        let slot = Operand::Symbol(name.clone());
+        let byval = byval.get(i).and_then(Option::as_ref);
+        scope.push(Statement::Var(arg_decl(&name, arg, byval, layout)?));
+        if let Some(ty) = byval {
+            scope.extend(copy_aggregate(arg, ty, &slot, spaces, layout)?);
+        } else {
+            let ty = move_type(arg.ty()).unwrap_or_default();
+            let value = match arg_scratch(arg) {
+                Some(scratch) => {
+                    let scratch = Operand::Reg(scratch);
+                    scope.push(
+                        Instruction::new(
+                            "selp",
+                            &["u16"],
+                            vec![
+                                scratch.clone(),
+                                Operand::Imm(1),
+                                Operand::Imm(0),
+                                Operand::from_value(arg)?,
+                            ],
+                        )
+                        .into(),
+                    );
+                    scratch
+                }
+                None => Operand::from_value(arg)?,
+            };
+            scope.push(Instruction::new("st", &["param", ty], vec![slot.addr(), value]).into());
+        }
+        params.push(Operand::Symbol(name));
+    }
+
+    let mut operands = vec![];
+    let mut after = vec![];
+    if let Some(ret) = ret {
+        let ty = move_type(ret.ty()).unwrap_or_default();
+        let slot = Operand::Symbol("retval0".to_string());
+        scope.push(Statement::Var(ret_decl("retval0", ret)?));
+        operands.push(Operand::List(vec![slot.clone()]));
+        match result_scratch(ret) {
+            Some(scratch) => {
+                let scratch = Operand::Reg(scratch);
+                after.push(
+                    Instruction::new("ld", &["param", ty], vec![scratch.clone(), slot.addr()])
+                        .into(),
+                );
+                after.push(
+                    Instruction::new(
+                        "setp",
+                        &["ne", "s16"],
+                        vec![Operand::from_value(ret)?, scratch, Operand::Imm(0)],
+                    )
+                    .into(),
+                );
+            }
+            None => after.push(
+                Instruction::new(
+                    "ld",
+                    &["param", ty],
+                    vec![Operand::from_value(ret)?, slot.addr()],
+                )
+                .into(),
+            ),
+        }
+    }
+    let call = match callee {
+        Callee::Direct(name) => {
+            operands.extend([Operand::symbol(name), Operand::List(params)]);
+            let modifiers: &[&str] = if uniform { &["uni"] } else { &[] };
+            Instruction::new("call", modifiers, operands)
+        }
+        Callee::Indirect(target) => {
+            let prototype = prototype(args, byval, ret, layout)?;
+            operands.extend([
+                Operand::from_value(target)?,
+                Operand::List(params),
+                Operand::Symbol(prototype.name),
+            ]);
+            Instruction::new("call", &[], operands)
+        }
+    };
+    scope.push(call.into());
+    scope.extend(after);
+
+    Ok(vec![Statement::Scope(scope)])
+}
+
+/// Copy the aggregate `ptr` points to into the `.param` array `slot`.
+fn copy_aggregate(
+    ptr: &Value,
+    ty: &Type,
+    slot: &Operand,
+    spaces: &AddressSpaces,
+    layout: &DataLayout,
+) -> Result<Vec<Statement>, CodegenError> {
+    let Some(scratch) = byval_scratch(ptr) else {
+        return Err(CodegenError::new(format!(
+            "unsupported byval argument {:?}",
+            ptr
+        )));
+    };
+    let src = Operand::from_value(ptr)?;
+    Ok(copy_bytes(
+        &scratch,
+        ty,
+        (spaces.of(ptr), &src),
+        (AddressSpace::Param, slot),
+        layout,
+    ))
+}
+
+/// `ld`/`st` pairs copying the `ty` at `src` to `dst` through the
+/// register `scratch`, each address given with its state space.
+pub fn copy_bytes(
+    scratch: &str,
+    ty: &Type,
+    (from, src): (AddressSpace, &Operand),
+    (to, dst): (AddressSpace, &Operand),
+    layout: &DataLayout,
+) -> Vec<Statement> {
+    let scratch = Operand::Reg(scratch.to_string());
+    let (unit, scratch_ty) = copy_unit(ty, layout);
+    let bits = match unit {
+        1 => "b8",
+        _ => scratch_ty.as_bits(),
+    };
+    let modifiers = |space: AddressSpace| match space.as_str() {
+        Some(space) => vec![space, bits],
+        None => vec![bits],
+    };
+    let (load, store) = (modifiers(from), modifiers(to));
+
+    let mut statements = vec![];
+    for offset in (0..layout.size_of(ty)).step_by(unit as usize) {
+        let offset = offset as i64;
+        statements.push(
+            Instruction::new(
+                "ld",
+                &load,
+                vec![
+                    scratch.clone(),
+                    Operand::Addr(Box::new(src.clone()), offset),
+                ],
+            )
+            .into(),
+        );
+        statements.push(
+            Instruction::new(
+                "st",
+                &store,
+                vec![
+                    Operand::Addr(Box::new(dst.clone()), offset),
+                    scratch.clone(),
+                ],
+            )
+            .into(),
+        );
+    }
+    statements
+}
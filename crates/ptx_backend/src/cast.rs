@@ -0,0 +1,144 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Conversions between integer, floating-point and pointer values.
+
+use ir_model::{Type, Value};
+
+use crate::ast::{Instruction, Operand};
+use crate::error::CodegenError;
+use crate::ptx_type::PTXType;
+use crate::utils::clean_operand;
+
+/// LLVM cast operations with a `cvt`/`mov` lowering. Address-space casts
+/// go through `cvta` and are lowered separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastKind {
+    ZExt,
+    SExt,
+    Trunc,
+    FPToSI,
+    FPToUI,
+    SIToFP,
+    UIToFP,
+    FPExt,
+    FPTrunc,
+    PtrToInt,
+    IntToPtr,
+    Bitcast,
+}
+
+/// Register holding the masked low bit of a truncation to `i1`.
+pub fn truncation_scratch(kind: CastKind, dst: &Value) -> Option<String> {
+    match (kind, dst.ty()) {
+        (CastKind::Trunc, Type::Int(1)) => dst
+            .local_name()
+            .map(|name| format!("{}$bit", clean_operand(name))),
+        _ => None,
+    }
+}
+
+/// Lower a cast, with types taken from the operands.
+pub fn lower_cast(
+    kind: CastKind,
+    dst: &Value,
+    src: &Value,
+) -> Result<Vec<Instruction>, CodegenError> {
+    use CastKind::*;
+
+    let d = Operand::from_value(dst)?;
+    let s = Operand::from_value(src)?;
+    let unsupported = || {
+        CodegenError::new(format!(
+            "unsupported {:?} from {:?} to {:?}",
+            kind,
+            src.ty(),
+            dst.ty()
+        ))
+    };
+    let int = |sign: char, ty: &Type| int_type(sign, ty).ok_or_else(unsupported);
+    let float = |ty: &Type| float_type(ty).ok_or_else(unsupported);
+    let cvt = |modifiers: &[&str]| {
+        vec![Instruction::new(
+            "cvt",
+            modifiers,
+            vec![d.clone(), s.clone()],
+        )]
+    };
+
+    let instrs = match kind {
+        // Predicates have no `cvt`; select the extended value instead.
+        ZExt | SExt if *src.ty() == Type::Int(1) => {
+            let (sign, ones) = if kind == ZExt { ('u', 1) } else { ('s', -1) };
+            vec![Instruction::new(
+                "selp",
+                &[&int(sign, dst.ty())?],
+                vec![d, Operand::Imm(ones), Operand::Imm(0), s],
+            )]
+        }
+        ZExt => cvt(&[&int('u', dst.ty())?, &int('u', src.ty())?]),
+        SExt => cvt(&[&int('s', dst.ty())?, &int('s', src.ty())?]),
+        Trunc if *dst.ty() == Type::Int(1) => {
+            let scratch = Operand::Reg(truncation_scratch(kind, dst).ok_or_else(unsupported)?);
+            let bits = PTXType::from_ir(src.ty())
+                .ok_or_else(unsupported)?
+                .as_bits();
+            vec![
+                Instruction::new("and", &[bits], vec![scratch.clone(), s, Operand::Imm(1)]),
+                Instruction::new("setp", &["ne", bits], vec![d, scratch, Operand::Imm(0)]),
+            ]
+        }
+        Trunc => cvt(&[&int('u', dst.ty())?, &int('u', src.ty())?]),
+        FPToSI => cvt(&["rzi", &int('s', dst.ty())?, float(src.ty())?]),
+        FPToUI => cvt(&["rzi", &int('u', dst.ty())?, float(src.ty())?]),
+        SIToFP => cvt(&["rn", float(dst.ty())?, &int('s', src.ty())?]),
+        UIToFP => cvt(&["rn", float(dst.ty())?, &int('u', src.ty())?]),
+        FPExt => cvt(&[float(dst.ty())?, float(src.ty())?]),
+        FPTrunc => cvt(&["rn", float(dst.ty())?, float(src.ty())?]),
+        PtrToInt | IntToPtr | Bitcast => {
+            let from = PTXType::from_ir(src.ty()).ok_or_else(unsupported)?;
+            let to = PTXType::from_ir(dst.ty()).ok_or_else(unsupported)?;
+            if from.as_bits() == to.as_bits() && from != PTXType::Pred {
+                vec![Instruction::new("mov", &[to.as_bits()], vec![d, s])]
+            } else if kind == PtrToInt {
+                cvt(&[&int('u', dst.ty())?, "u64"])
+            } else if kind == IntToPtr {
+                cvt(&["u64", &int('u', src.ty())?])
+            } else {
+                return Err(unsupported());
+            }
+        }
+    };
+    Ok(instrs)
+}
+
+/// `u8`/`s32`... for an integer type of a width `cvt` supports.
+fn int_type(sign: char, ty: &Type) -> Option<String> {
+    match ty.int_bits()? {
+        bits @ (8 | 16 | 32 | 64) => Some(format!("{sign}{bits}")),
+        _ => None,
+    }
+}
+
+fn float_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Half => Some("f16"),
+        Type::Float => Some("f32"),
+        Type::Double => Some("f64"),
+        _ => None,
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+// Type sizes, alignments and field offsets from an LLVM data layout string.
+
+use std::collections::HashMap;
+
+use ir_model::Type;
+
+/// Layout used by the NVPTX backend for 64-bit targets, assumed when a
+/// module carries no `target datalayout`.
+pub const NVPTX64_LAYOUT: &str = "e-i64:64-i128:128-v16:16-v32:32-n16:32:64";
+
+/// Memory layout of IR types, in bytes.
+#[derive(Debug, Clone)]
+pub struct DataLayout {
+    /// ABI alignment of integer, float and vector types keyed by bit width.
+    ints: Vec<(u32, u64)>,
+    floats: Vec<(u32, u64)>,
+    vectors: Vec<(u32, u64)>,
+    /// Pointer size and ABI alignment by address space.
+    pointers: HashMap<u32, (u64, u64)>,
+    aggregate_align: u64,
+    structs: HashMap<String, Type>,
+}
+
+impl Default for DataLayout {
+    fn default() -> Self {
+        DataLayout::new(NVPTX64_LAYOUT, HashMap::new())
+    }
+}
+
+impl DataLayout {
+    /// Parse a data layout string, starting from LLVM's defaults.
+    ///
+    /// `structs` holds the definitions of the named struct types the IR
+    /// refers to.
+    pub fn new(spec: &str, structs: HashMap<String, Type>) -> Self {
+        let mut layout = DataLayout {
+            ints: vec![(1, 1), (8, 1), (16, 2), (32, 4), (64, 4)],
+            floats: vec![(16, 2), (32, 4), (64, 8), (128, 16)],
+            vectors: vec![(64, 8), (128, 16)],
+            pointers: HashMap::from([(0, (8, 8))]),
+            aggregate_align: 1,
+            structs,
+        };
+
+        let spec = if spec.is_empty() {
+            NVPTX64_LAYOUT
+        } else {
+            spec
+        };
+        for entry in spec.split('-') {
+            let (kind, rest) = entry.split_at(
+                entry
+                    .find(|c: char| c.is_ascii_digit() || c == ':')
+                    .unwrap_or(entry.len()),
+            );
+            let mut fields = rest.split(':');
+            let first = fields.next().unwrap_or("");
+            let nums: Vec<u64> = fields.filter_map(|f| f.parse().ok()).collect();
+            let abi = |i: usize| nums.get(i).map(|bits| bits / 8);
+
+            match kind {
+                "i" | "f" | "v" => {
+                    let (Ok(bits), Some(align)) = (first.parse::<u32>(), abi(0)) else {
+                        continue;
+                    };
+                    let table = match kind {
+                        "i" => &mut layout.ints,
+                        "f" => &mut layout.floats,
+                        _ => &mut layout.vectors,
+                    };
+                    table.retain(|(b, _)| *b != bits);
+                    table.push((bits, align));
+                    table.sort();
+                }
+                "p" => {
+                    let space = first.parse().unwrap_or(0);
+                    if let (Some(size), Some(align)) = (abi(0), abi(1)) {
+                        layout.pointers.insert(space, (size, align));
+                    }
+                }
+                "a" => {
+                    if let Some(align) = abi(0) {
+                        layout.aggregate_align = align.max(1);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        layout
+    }
+
+    /// Layout of an LLVM module, with its named struct definitions.
+    pub fn from_module(module: &llvm_ir::Module) -> Self {
+        DataLayout::new(
+            &module.data_layout.layout_str,
+            llvm_parser::lower_struct_types(&module.types),
+        )
+    }
+
+    /// Number of bytes between consecutive elements of type `ty` in memory
+    /// (LLVM's alloc size).
+    pub fn size_of(&self, ty: &Type) -> u64 {
+        align_to(self.store_size(ty), self.align_of(ty))
+    }
+
+    /// ABI alignment of `ty`, in bytes.
+    pub fn align_of(&self, ty: &Type) -> u64 {
+        match ty {
+            Type::Int(bits) => {
+                lookup(&self.ints, *bits).unwrap_or_else(|| u64::from(bits.div_ceil(8)))
+            }
+            Type::Half => lookup(&self.floats, 16).unwrap_or(2),
+            Type::Float => lookup(&self.floats, 32).unwrap_or(4),
+            Type::Double => lookup(&self.floats, 64).unwrap_or(8),
+            Type::Ptr { addr_space, .. } => self.pointer(*addr_space).1,
+            Type::Vector { .. } => {
+                let bits = self.store_size(ty) * 8;
+                self.vectors
+                    .iter()
+                    .find(|(b, _)| u64::from(*b) == bits)
+                    .map(|(_, a)| *a)
+                    .unwrap_or_else(|| self.store_size(ty).next_power_of_two().max(1))
+            }
+            Type::Array { elem, .. } => self.align_of(elem),
+            Type::Struct { fields, packed } => {
+                if *packed {
+                    1
+                } else {
+                    fields
+                        .iter()
+                        .map(|f| self.align_of(f))
+                        .fold(self.aggregate_align, u64::max)
+                }
+            }
+            Type::NamedStruct(name) => self.structs.get(name).map_or(1, |s| self.align_of(s)),
+            _ => 1,
+        }
+    }
+
+    /// Byte offset of field `index` within a struct type.
+    pub fn field_offset(&self, ty: &Type, index: usize) -> Option<u64> {
+        let (fields, packed) = self.struct_fields(ty)?;
+        let mut offset = 0;
+        for (i, field) in fields.iter().enumerate() {
+            if !packed {
+                offset = align_to(offset, self.align_of(field));
+            }
+            if i == index {
+                return Some(offset);
+            }
+            offset += self.size_of(field);
+        }
+        None
+    }
+
+    /// Field types of a (possibly named) struct type.
+    pub fn struct_fields<'a>(&'a self, ty: &'a Type) -> Option<(&'a [Type], bool)> {
+        match ty {
+            Type::Struct { fields, packed } => Some((fields, *packed)),
+            Type::NamedStruct(name) => self.struct_fields(self.structs.get(name)?),
+            _ => None,
+        }
+    }
+
+    fn store_size(&self, ty: &Type) -> u64 {
+        match ty {
+            Type::Int(bits) => u64::from(bits.div_ceil(8)),
+            Type::Half => 2,
+            Type::Float => 4,
+            Type::Double => 8,
+            Type::Ptr { addr_space, .. } => self.pointer(*addr_space).0,
+            Type::Vector { elem, len } => self.store_size(elem) * *len as u64,
+            Type::Array { elem, len } => self.size_of(elem) * *len as u64,
+            Type::Struct { .. } | Type::NamedStruct(_) => {
+                let Some((fields, packed)) = self.struct_fields(ty) else {
+                    return 0;
+                };
+                let end = fields.len().checked_sub(1).map_or(0, |last| {
+                    self.field_offset(ty, last).unwrap_or(0) + self.size_of(&fields[last])
+                });
+                if packed {
+                    end
+                } else {
+                    align_to(end, self.align_of(ty))
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn pointer(&self, addr_space: u32) -> (u64, u64) {
+        self.pointers
+            .get(&addr_space)
+            .or_else(|| self.pointers.get(&0))
+            .copied()
+            .unwrap_or((8, 8))
+    }
+}
+
+/// ABI alignment for a bit width: an exact entry, else the smallest wider
+/// one, else the widest one, as LLVM does for integers.
+fn lookup(table: &[(u32, u64)], bits: u32) -> Option<u64> {
+    table
+        .iter()
+        .find(|(b, _)| *b >= bits)
+        .or(table.last())
+        .map(|(_, align)| *align)
+}
+
+fn align_to(value: u64, align: u64) -> u64 {
+    value.div_ceil(align.max(1)) * align.max(1)
+}
@@ -0,0 +1,91 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Errors raised while lowering IR to PTX.
+
+use std::fmt;
+
+use ir_model::Instruction;
+
+/// Something the backend cannot lower, with the place in the IR where it
+/// was found.
+///
+/// The location is filled in as the error travels up: instruction lowering
+/// knows the message, `lower_function` adds the function, block and
+/// instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub function: Option<String>,
+    pub block: Option<String>,
+    /// The offending instruction, in debug form.
+    pub instruction: Option<String>,
+    pub message: String,
+}
+
+impl CodegenError {
+    pub fn new(message: impl Into<String>) -> Self {
+        CodegenError {
+            function: None,
+            block: None,
+            instruction: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn in_function(mut self, name: &str) -> Self {
+        self.function.get_or_insert_with(|| name.to_string());
+        self
+    }
+
+    pub fn in_block(mut self, name: &str) -> Self {
+        self.block.get_or_insert_with(|| name.to_string());
+        self
+    }
+
+    pub fn at(mut self, instr: &Instruction) -> Self {
+        self.instruction.get_or_insert_with(|| match instr {
+            // Already the debug form of the LLVM instruction.
+            Instruction::Unhandled { text, .. } => text.clone(),
+            other => format!("{other:?}"),
+        });
+        self
+    }
+
+    /// The message and the instruction, without the location. This is
+    /// the comment permissive mode leaves in place of the instruction.
+    pub fn summary(&self) -> String {
+        match &self.instruction {
+            Some(instruction) => format!("{}: {}", self.message, instruction),
+            None => self.message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(function) = &self.function {
+            write!(f, "in function `{function}`")?;
+            if let Some(block) = &self.block {
+                write!(f, ", block `{block}`")?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+impl std::error::Error for CodegenError {}
@@ -0,0 +1,217 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Stack frame of a function: allocas promoted to registers, or laid out
+// in a `.local` depot.
+
+use std::collections::HashMap;
+
+use ir_model::mem2reg::promotable;
+use ir_model::{Instruction, Type, Value};
+
+use crate::addr_space::{AddressSpace, AddressSpaces};
+use crate::ast::{self, Operand, Statement, VarDecl};
+use crate::data_layout::DataLayout;
+use crate::error::CodegenError;
+use crate::ptx_type::PTXType;
+use crate::type_map::TypeMap;
+use crate::utils::clean_operand;
+
+/// `.local` array holding the allocas that stay in memory.
+pub const DEPOT: &str = "__local_depot";
+
+/// Where an alloca lives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    /// In the alloca's own register, retyped to the allocated scalar.
+    Register(PTXType),
+    /// At a byte offset in the depot.
+    Depot(u64),
+}
+
+/// Slots of the allocas of a function, keyed by register name.
+#[derive(Debug, Default)]
+pub struct Frame {
+    slots: HashMap<String, Slot>,
+    size: u64,
+    align: u64,
+}
+
+impl Frame {
+    /// Lay out the allocas of `instrs`.
+    ///
+    /// A scalar alloca whose address is only used to load and store whole
+    /// values of its type is promoted: PTX registers can be assigned more
+    /// than once, so the slot becomes a register and its loads and stores
+    /// `mov`s. Any other alloca gets an aligned offset in the depot.
+    pub fn new(instrs: &[&Instruction], layout: &DataLayout) -> Self {
+        let mut frame = Frame::default();
+        for instr in instrs {
+            let Instruction::Alloca { dst, ty, align, .. } = instr else {
+                continue;
+            };
+            let Some(name) = dst.local_name() else {
+                continue;
+            };
+            let slot = match PTXType::from_ir(ty) {
+                Some(reg_ty) if promotable(name, ty, instrs) => Slot::Register(reg_ty),
+                _ => {
+                    let align = layout.align_of(ty).max(u64::from(*align));
+                    Slot::Depot(frame.reserve(layout.size_of(ty), align))
+                }
+            };
+            frame.slots.insert(clean_operand(name), slot);
+        }
+        frame
+    }
+
+    /// Offset of `size` new bytes aligned to `align` in the depot.
+    fn reserve(&mut self, size: u64, align: u64) -> u64 {
+        let offset = self.size.next_multiple_of(align);
+        self.size = offset + size;
+        self.align = self.align.max(align);
+        offset
+    }
+
+    /// Give the kernel `byval` parameter `param` a depot slot to copy its
+    /// aggregate into. A kernel's `.param` space only has generic
+    /// addresses from PTX 7.7, so an aggregate whose address escapes is
+    /// used from the copy instead.
+    pub fn copy_byval(&mut self, param: &Value, ty: &Type, layout: &DataLayout) {
+        let Some(name) = param.local_name() else {
+            return;
+        };
+        let offset = self.reserve(layout.size_of(ty), layout.align_of(ty));
+        self.slots.insert(clean_operand(name), Slot::Depot(offset));
+    }
+
+    /// Depot offset of the copy of the `byval` parameter `param`.
+    pub fn byval_copy(&self, param: &Value) -> Option<u64> {
+        match self.slot(param)? {
+            Slot::Depot(offset) => Some(offset),
+            Slot::Register(_) => None,
+        }
+    }
+
+    /// `.local .align N .b8 __local_depot[size]`, if any alloca is in
+    /// memory.
+    pub fn depot(&self) -> Option<VarDecl> {
+        (self.size > 0).then(|| VarDecl {
+            space: AddressSpace::Local,
+            align: Some(self.align),
+            ty: "b8".to_string(),
+            name: DEPOT.to_string(),
+            len: Some(self.size),
+            init: vec![],
+        })
+    }
+
+    /// Give promoted allocas the register type of their value.
+    pub fn retype(&self, type_map: &mut TypeMap) {
+        for (name, slot) in &self.slots {
+            if let Slot::Register(ty) = slot {
+                type_map.insert(name, *ty);
+            }
+        }
+    }
+
+    fn slot(&self, ptr: &Value) -> Option<Slot> {
+        self.slots.get(&clean_operand(ptr.local_name()?)).copied()
+    }
+
+    /// Lower an alloca, or a load or store of a promoted alloca. `None`
+    /// for any other instruction.
+    pub fn lower(
+        &self,
+        instr: &Instruction,
+        spaces: &AddressSpaces,
+    ) -> Result<Option<Vec<Statement>>, CodegenError> {
+        let mov = |ty: PTXType, dst: Operand, src: Operand| -> Statement {
+            let ty = match ty {
+                PTXType::F16 => "b16",
+                ty => ty.as_str(),
+            };
+            ast::Instruction::new("mov", &[ty], vec![dst, src]).into()
+        };
+        let slot = match instr {
+            Instruction::Alloca { dst: ptr, .. }
+            | Instruction::Load { src: ptr, .. }
+            | Instruction::Store { dst: ptr, .. } => self.slot(ptr),
+            _ => None,
+        };
+        let Some(slot) = slot else {
+            return Ok(None);
+        };
+        Ok(match instr {
+            Instruction::Alloca { dst, .. } => Some(match slot {
+                Slot::Register(_) => vec![],
+                Slot::Depot(offset) => {
+                    let reg = Operand::from_value(dst)?;
+                    let mut statements: Vec<Statement> = depot_address(&reg, offset)
+                        .into_iter()
+                        .map(Statement::from)
+                        .collect();
+                    if spaces.of(dst) == AddressSpace::Generic {
+                        statements.push(
+                            ast::Instruction::new(
+                                "cvta",
+                                &["local", "u64"],
+                                vec![reg.clone(), reg],
+                            )
+                            .into(),
+                        );
+                    }
+                    statements
+                }
+            }),
+            Instruction::Load { dst, src, .. } => match slot {
+                Slot::Register(ty) => Some(vec![mov(
+                    ty,
+                    Operand::from_value(dst)?,
+                    Operand::from_value(src)?,
+                )]),
+                Slot::Depot(_) => None,
+            },
+            Instruction::Store { dst, value, .. } => match slot {
+                Slot::Register(ty) => Some(vec![mov(
+                    ty,
+                    Operand::from_value(dst)?,
+                    Operand::from_value(value)?,
+                )]),
+                Slot::Depot(_) => None,
+            },
+            _ => None,
+        })
+    }
+}
+
+/// Put the `.local` address of the depot slot at `offset` in `reg`.
+pub fn depot_address(reg: &Operand, offset: u64) -> Vec<ast::Instruction> {
+    let mut instrs = vec![ast::Instruction::new(
+        "mov",
+        &["u64"],
+        vec![reg.clone(), Operand::Symbol(DEPOT.to_string())],
+    )];
+    if offset > 0 {
+        instrs.push(ast::Instruction::new(
+            "add",
+            &["s64"],
+            vec![reg.clone(), reg.clone(), Operand::Imm(offset as i64)],
+        ));
+    }
+    instrs
+}
@@ -0,0 +1,155 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+// GetElementPtr lowering to 64-bit address arithmetic.
+
+use ir_model::{Type, Value};
+
+use crate::ast::{Instruction, Operand};
+use crate::data_layout::DataLayout;
+use crate::error::CodegenError;
+use crate::ptx_type::PTXType;
+
+/// Byte offset of a GEP, split into the folded constant part and one
+/// `(index, stride)` term per non-constant index.
+pub fn gep_offset<'a>(
+    source_ty: &Type,
+    indices: &'a [Value],
+    layout: &DataLayout,
+) -> (i64, Vec<(&'a Value, i64)>) {
+    let mut constant = 0i64;
+    let mut terms = vec![];
+    let mut ty = source_ty;
+
+    for (i, index) in indices.iter().enumerate() {
+        let value = match index {
+            Value::Null { .. } | Value::Undef { .. } => Some(0),
+            _ => index.as_signed(),
+        };
+
+        // The first index steps over whole source elements; the others
+        // walk into the aggregate.
+        let stride = if i == 0 {
+            layout.size_of(ty)
+        } else if let Some((fields, _)) = layout.struct_fields(ty) {
+            let field = value.unwrap_or(0) as usize;
+            constant += layout.field_offset(ty, field).unwrap_or(0) as i64;
+            ty = fields.get(field).unwrap_or(ty);
+            continue;
+        } else {
+            if let Type::Array { elem, .. } | Type::Vector { elem, .. } = ty {
+                ty = elem;
+            }
+            layout.size_of(ty)
+        } as i64;
+
+        match value {
+            Some(value) => constant = constant.wrapping_add(value.wrapping_mul(stride)),
+            None if stride != 0 => terms.push((index, stride)),
+            None => {}
+        }
+    }
+
+    (constant, terms)
+}
+
+/// Whether an index must be sign-extended into a scratch register before
+/// being scaled, because neither `mad.wide.s32` nor `mad.lo.s64` fits.
+pub fn needs_index_scratch(index: &Value, stride: i64) -> bool {
+    match PTXType::from_ir(index.ty()) {
+        Some(PTXType::S64) => false,
+        Some(PTXType::S32) => i32::try_from(stride).is_err(),
+        _ => true,
+    }
+}
+
+/// Name of the 64-bit scratch register used by [`needs_index_scratch`].
+pub fn index_scratch(dst: &Value) -> String {
+    format!("{}$idx", dst.local_name().unwrap_or("gep"))
+}
+
+/// Lower a GEP to `mad.wide.s32`/`mad.lo.s64`/`add.s64` on the base
+/// address.
+pub fn lower_gep(
+    dst: &Value,
+    base: &Value,
+    source_ty: &Type,
+    indices: &[Value],
+    layout: &DataLayout,
+) -> Result<Vec<Instruction>, CodegenError> {
+    let (constant, terms) = gep_offset(source_ty, indices, layout);
+    let d = Operand::from_value(dst)?;
+    let mut instrs = vec![];
+
+    // Arithmetic only takes registers, so symbols and null go through a mov.
+    let mut acc = match base {
+        Value::Local { .. } => Operand::from_value(base)?,
+        _ => {
+            instrs.push(Instruction::new(
+                "mov",
+                &["u64"],
+                vec![d.clone(), Operand::from_value(base)?],
+            ));
+            d.clone()
+        }
+    };
+
+    for (index, stride) in terms {
+        let idx = Operand::from_value(index)?;
+        let scale = Operand::Imm(stride);
+        if needs_index_scratch(index, stride) {
+            let scratch = Operand::reg(&index_scratch(dst));
+            let from = PTXType::memory_from_ir(index.ty()).unwrap_or(PTXType::S32);
+            instrs.push(Instruction::new(
+                "cvt",
+                &["s64", from.as_str()],
+                vec![scratch.clone(), idx],
+            ));
+            instrs.push(Instruction::new(
+                "mad",
+                &["lo", "s64"],
+                vec![d.clone(), scratch, scale, acc],
+            ));
+        } else if PTXType::from_ir(index.ty()) == Some(PTXType::S32) {
+            instrs.push(Instruction::new(
+                "mad",
+                &["wide", "s32"],
+                vec![d.clone(), idx, scale, acc],
+            ));
+        } else if stride == 1 {
+            instrs.push(Instruction::new("add", &["s64"], vec![d.clone(), acc, idx]));
+        } else {
+            instrs.push(Instruction::new(
+                "mad",
+                &["lo", "s64"],
+                vec![d.clone(), idx, scale, acc],
+            ));
+        }
+        acc = d.clone();
+    }
+
+    if constant != 0 {
+        instrs.push(Instruction::new(
+            "add",
+            &["s64"],
+            vec![d.clone(), acc, Operand::Imm(constant)],
+        ));
+    } else if acc != d {
+        instrs.push(Instruction::new("mov", &["u64"], vec![d, acc]));
+    }
+
+    Ok(instrs)
+}
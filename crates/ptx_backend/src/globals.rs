@@ -0,0 +1,301 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Module-level variables: their declarations, and the addresses of
+// variables and functions taken in function bodies.
+
+use ir_model::{GlobalVar, Initializer, Instruction, Type, Value};
+
+use crate::addr_space::{AddressSpace, AddressSpaces};
+use crate::ast::{self, Operand, Statement, VarDecl};
+use crate::data_layout::DataLayout;
+use crate::error::CodegenError;
+use crate::utils::clean_operand;
+
+/// State space a variable is declared in. Variables in the generic
+/// address space live in global memory.
+pub fn variable_space(global: &GlobalVar) -> AddressSpace {
+    match global.addr_space {
+        0 => AddressSpace::Global,
+        n => AddressSpace::from_llvm(n),
+    }
+}
+
+/// Register holding the address of global `name`, taken once on entry:
+/// `name$addr` for a generic address (and for functions), `name$shared`
+/// and the like for an address in a specific state space.
+pub fn global_address_reg(name: &str, ty: &Type) -> String {
+    let space = ty
+        .addr_space()
+        .map_or(AddressSpace::Generic, AddressSpace::from_llvm);
+    format!(
+        "{}${}",
+        clean_operand(name),
+        space.as_str().unwrap_or("addr")
+    )
+}
+
+/// Globals whose address `instrs` use as a value, in order of first use.
+/// Loads and stores address variables by their symbol instead.
+pub fn address_uses<'a>(
+    instrs: impl IntoIterator<Item = &'a Instruction>,
+    spaces: &AddressSpaces,
+) -> Vec<&'a Value> {
+    let mut uses: Vec<&Value> = vec![];
+    let mut regs: Vec<String> = vec![];
+    for instr in instrs {
+        let direct = match instr {
+            Instruction::Load { src, .. } => Some(src),
+            Instruction::Store { dst, .. } => Some(dst),
+            _ => None,
+        }
+        .filter(|ptr| spaces.variable(ptr).is_some());
+        for value in instr.values() {
+            let Value::Global { name, ty } = value else {
+                continue;
+            };
+            if direct.is_some_and(|d| std::ptr::eq(d, value)) {
+                continue;
+            }
+            let reg = global_address_reg(name, ty);
+            if !regs.contains(&reg) {
+                regs.push(reg);
+                uses.push(value);
+            }
+        }
+    }
+    uses
+}
+
+/// `mov` the address of each global in `uses` into its register. A
+/// variable used as a generic pointer is converted from its state space.
+pub fn load_addresses(uses: &[&Value], spaces: &AddressSpaces) -> Vec<Statement> {
+    let mut statements = vec![];
+    for value in uses {
+        let Value::Global { name, ty } = value else {
+            continue;
+        };
+        let reg = Operand::Reg(global_address_reg(name, ty));
+        statements.push(
+            ast::Instruction::new("mov", &["u64"], vec![reg.clone(), Operand::symbol(name)]).into(),
+        );
+        if ty.addr_space() == Some(0)
+            && let Some(space) = spaces.variable(value).and_then(|s| s.as_str())
+        {
+            statements.push(
+                ast::Instruction::new("cvta", &[space, "u64"], vec![reg.clone(), reg]).into(),
+            );
+        }
+    }
+    statements
+}
+
+/// `.global`, `.const` or `.shared` declaration of a variable, with its
+/// initializer.
+///
+/// Scalars and arrays of one scalar type are declared with that type and
+/// initialized element by element, so that they can hold the addresses
+/// of other globals. Anything else is an array of bytes. `.shared`
+/// variables cannot be initialized, and all-zero initializers are left
+/// out since variables start zeroed.
+pub fn declare_global(
+    global: &GlobalVar,
+    globals: &[GlobalVar],
+    layout: &DataLayout,
+) -> Result<VarDecl, CodegenError> {
+    let space = variable_space(global);
+    if !matches!(
+        space,
+        AddressSpace::Global | AddressSpace::Const | AddressSpace::Shared
+    ) {
+        return Err(CodegenError::new(format!(
+            "unsupported address space {} for global {}",
+            global.addr_space, global.name
+        )));
+    }
+    let align = match global.align {
+        0 => layout.align_of(&global.ty),
+        align => u64::from(align),
+    };
+    let size = layout.size_of(&global.ty);
+    let init = global
+        .init
+        .as_ref()
+        .filter(|init| space != AddressSpace::Shared && !is_zero(init));
+
+    let mut decl = VarDecl {
+        space,
+        align: Some(align),
+        ty: "b8".to_string(),
+        name: clean_operand(&global.name),
+        len: Some(size),
+        init: vec![],
+    };
+    match element_type(&global.ty) {
+        Some((ty, scalar, count)) if count * layout.size_of(scalar) == size => {
+            decl.ty = ty.to_string();
+            decl.len =
+                matches!(global.ty, Type::Array { .. } | Type::Vector { .. }).then_some(count);
+            if let Some(init) = init {
+                elements(init, ty, globals, &mut decl.init)?;
+            }
+        }
+        _ => {
+            if let Some(init) = init {
+                let mut bytes = vec![0; size as usize];
+                write_bytes(init, layout, &mut bytes, 0)?;
+                decl.init = bytes.into_iter().map(|b| Operand::Imm(b.into())).collect();
+            }
+        }
+    }
+    Ok(decl)
+}
+
+/// PTX type of a scalar, or of the scalars of a (nested) array or vector,
+/// with the scalar type and their count.
+fn element_type(ty: &Type) -> Option<(&'static str, &Type, u64)> {
+    let ptx = match ty {
+        Type::Int(1 | 8) => "b8",
+        Type::Int(16) => "u16",
+        Type::Int(32) => "u32",
+        Type::Int(64) | Type::Ptr { .. } => "u64",
+        Type::Half => "b16",
+        Type::Float => "f32",
+        Type::Double => "f64",
+        Type::Array { elem, len } | Type::Vector { elem, len } => {
+            let (ptx, scalar, count) = element_type(elem)?;
+            return Some((ptx, scalar, count * *len as u64));
+        }
+        _ => return None,
+    };
+    Some((ptx, ty, 1))
+}
+
+fn is_zero(init: &Initializer) -> bool {
+    match init {
+        Initializer::Zero(_) => true,
+        Initializer::Scalar(Value::ConstInt { value, .. }) => *value == 0,
+        Initializer::Scalar(Value::ConstFloat { value, .. }) => value.to_bits() == 0,
+        Initializer::Scalar(Value::Null { .. } | Value::Undef { .. }) => true,
+        Initializer::Scalar(_) => false,
+        Initializer::Aggregate(_, items) => items.iter().all(is_zero),
+    }
+}
+
+/// Append the scalars of `init`, of PTX type `ty`, to `out`.
+fn elements(
+    init: &Initializer,
+    ty: &str,
+    globals: &[GlobalVar],
+    out: &mut Vec<Operand>,
+) -> Result<(), CodegenError> {
+    match init {
+        Initializer::Scalar(value) => out.push(scalar(value, globals)?),
+        Initializer::Zero(zero_ty) => {
+            let count = element_type(zero_ty).map_or(1, |(_, _, count)| count);
+            let zero = match ty {
+                "f32" => Operand::F32(0.0),
+                "f64" => Operand::F64(0.0),
+                _ => Operand::Imm(0),
+            };
+            out.extend(std::iter::repeat_n(zero, count as usize));
+        }
+        Initializer::Aggregate(_, items) => {
+            for item in items {
+                elements(item, ty, globals, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Initializer operand of a scalar. The address of a variable taken as a
+/// generic pointer is spelled `generic(name)`.
+fn scalar(value: &Value, globals: &[GlobalVar]) -> Result<Operand, CodegenError> {
+    Ok(match value {
+        Value::ConstInt { value, .. } => Operand::Imm(*value as i64),
+        Value::ConstFloat {
+            value,
+            ty: Type::Float,
+        } => Operand::F32(*value as f32),
+        Value::ConstFloat {
+            value,
+            ty: Type::Double,
+        } => Operand::F64(*value),
+        Value::Null { .. } | Value::Undef { .. } => Operand::Imm(0),
+        Value::Global { name, ty }
+            if ty.addr_space() == Some(0) && globals.iter().any(|g| g.name == *name) =>
+        {
+            Operand::Symbol(format!("generic({})", clean_operand(name)))
+        }
+        Value::Global { name, .. } => Operand::symbol(name),
+        other => {
+            return Err(CodegenError::new(format!(
+                "unsupported global initializer {other:?}"
+            )));
+        }
+    })
+}
+
+/// Write the little-endian bytes of `init` into `bytes` at `offset`.
+fn write_bytes(
+    init: &Initializer,
+    layout: &DataLayout,
+    bytes: &mut [u8],
+    offset: u64,
+) -> Result<(), CodegenError> {
+    let mut put = |data: &[u8]| {
+        let start = offset as usize;
+        if let Some(slot) = bytes.get_mut(start..start + data.len()) {
+            slot.copy_from_slice(data);
+        }
+    };
+    match init {
+        Initializer::Zero(_) | Initializer::Scalar(Value::Null { .. } | Value::Undef { .. }) => {}
+        Initializer::Scalar(Value::ConstInt { value, ty }) => {
+            let width = ty.int_bits().unwrap_or(64).div_ceil(8) as usize;
+            put(&value.to_le_bytes()[..width.min(8)]);
+        }
+        Initializer::Scalar(Value::ConstFloat {
+            value,
+            ty: Type::Float,
+        }) => put(&(*value as f32).to_le_bytes()),
+        Initializer::Scalar(Value::ConstFloat {
+            value,
+            ty: Type::Double,
+        }) => put(&value.to_le_bytes()),
+        Initializer::Scalar(other) => {
+            return Err(CodegenError::new(format!(
+                "unsupported value {other:?} in an aggregate initializer"
+            )));
+        }
+        Initializer::Aggregate(ty, items) => {
+            for (i, item) in items.iter().enumerate() {
+                let at = match ty {
+                    Type::Array { elem, .. } | Type::Vector { elem, .. } => {
+                        i as u64 * layout.size_of(elem)
+                    }
+                    _ => layout.field_offset(ty, i).ok_or_else(|| {
+                        CodegenError::new(format!("no field {i} in initializer of {ty:?}"))
+                    })?,
+                };
+                write_bytes(item, layout, bytes, offset + at)?;
+            }
+        }
+    }
+    Ok(())
+}
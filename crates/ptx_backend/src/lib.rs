@@ -15,53 +15,287 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod addr_space;
+pub mod ast;
+pub mod atomic;
+pub mod call;
+pub mod cast;
+pub mod data_layout;
+pub mod error;
+pub mod frame;
+pub mod gep;
+pub mod globals;
+pub mod module;
+pub mod phi_elim;
+pub mod printer;
+pub mod printf;
 pub mod ptx_type;
-pub mod utils;
+pub mod switch;
+pub mod type_infer;
 pub mod type_map;
+pub mod utils;
 
+use crate::addr_space::{AddressSpace, AddressSpaces, infer_address_spaces_from};
+use crate::ast::{Operand, Statement, VarDecl};
+use crate::atomic::{
+    cas_scratch, drop_unused_results, lower_atomic_rmw, lower_cmpxchg, membar_level, neg_scratch,
+    scope_name,
+};
+use crate::call::{
+    aggregate_param, byval_scratch, call_scratch, copy_bytes, copy_unit, lower_call, prototype,
+    uniform_blocks,
+};
+use crate::cast::{CastKind, lower_cast, truncation_scratch};
+use crate::data_layout::DataLayout;
+use crate::error::CodegenError;
+use crate::frame::{Frame, depot_address};
+use crate::gep::{gep_offset, index_scratch, lower_gep, needs_index_scratch};
+use crate::globals::{address_uses, declare_global, global_address_reg, load_addresses};
+use crate::module::{CodegenOptions, FunctionKind, Linkage, OnUnreachable, PtxFunction, PtxModule};
+use crate::phi_elim::eliminate_phis;
+use crate::printer::print_statements;
+use crate::printf::{VPRINTF, buffer_size, is_printf, lower_printf, printf_scratch};
 use crate::ptx_type::PTXType;
-use crate::utils::{clean_operand, get_register_type};
-use ir_model::Instruction;
-use crate::type_map::{TypeMap, declare_registers_from_typemap};
-
+use crate::switch::{condition_mask, jump_table, lower_switch, switch_scratch};
+use crate::type_infer::{infer_types, record};
+use crate::type_map::{TypeMap, group_registers};
+use crate::utils::{clean_operand, label_name};
+use anyhow::Result;
+use ir_model::{
+    BarrierKind, BasicBlock, Callee, Function, GlobalVar, Instruction, MemoryOrdering, Signature,
+    SpecialRegister, Type, Value,
+};
 
+/// Lower one function into a `.entry` kernel or a `.func` device function,
+/// as its `kernel` flag says.
+///
+/// Instructions the backend cannot lower are an error, unless
+/// `options.permissive` is set, in which case they are emitted as comments.
+/// `globals` are the module's variables the function may refer to.
 pub fn lower_function(
-    name: &str,
-    all_instrs: &[(String, Vec<Instruction>)],
-    target: &str,
-) -> Vec<String> {
+    function: &Function,
+    globals: &[GlobalVar],
+    layout: &DataLayout,
+    options: &CodegenOptions,
+) -> Result<PtxFunction, CodegenError> {
+    let name = function.name.as_str();
+    let kind = match function.kernel {
+        true => FunctionKind::Entry,
+        false => FunctionKind::Func,
+    };
+    let signature = &function.signature;
     let mut output = vec![];
+    let params = &signature.params;
+    let param_decls = declare_params(name, signature, layout)?;
+    let ret = declare_return(name, kind, &signature.ret)?;
 
-    output.push(format!("// Function: {}", name));
-    output.push(emit_header(target));
-    output.push(format!(".entry {} {{", clean_operand(name)));
-
+    let all_instrs = &drop_unused_results(&eliminate_phis(&function.blocks));
+    // Only kernels are known to receive global memory from the host. The
+    // address of a `byval` parameter is in the parameter space of a kernel
+    // and on the stack of a device function.
+    let param_spaces: Vec<(Value, AddressSpace)> = params
+        .iter()
+        .enumerate()
+        .filter_map(|(i, param)| {
+            let space = match (kind, signature.byval(i)) {
+                (FunctionKind::Entry, None) => AddressSpace::Global,
+                (FunctionKind::Entry, Some(_)) => AddressSpace::Param,
+                (FunctionKind::Func, Some(_)) => AddressSpace::Local,
+                (FunctionKind::Func, None) => return None,
+            };
+            Some((param.clone(), space))
+        })
+        .collect();
+    let spaces = infer_address_spaces_from(all_instrs, &param_spaces).with_variables(globals);
     let flat_instrs: Vec<&Instruction> = all_instrs
         .iter()
-        .flat_map(|(_, instrs)| instrs.iter())
+        .flat_map(|block| block.instrs.iter())
         .collect();
 
-    let mut type_map = TypeMap::new();
+    let in_function = |e: anyhow::Error| CodegenError::new(e.to_string()).in_function(name);
+    let mut type_map = infer_types(all_instrs).map_err(in_function)?;
+    for param in params {
+        record(&mut type_map, param, "entry").map_err(in_function)?;
+    }
+    let mut printf_buffer = 0;
     for instr in &flat_instrs {
-        for operand in instr.used_operands() {
-            if let Some(ty_str) = get_register_type(instr, &operand) {
-                let ptx_ty = PTXType::from_str(ty_str);
-                type_map.insert(&clean_operand(&operand), ptx_ty);
+        if let Instruction::CmpXchg { dst, expected, .. } = instr {
+            let (old, ok) = cas_scratch(dst);
+            if let Some(ty) = PTXType::from_ir(expected.ty()) {
+                type_map.insert(&old, ty);
+            }
+            type_map.insert(&ok, PTXType::Pred);
+        }
+        if let Instruction::Shl { dst, rhs, .. }
+        | Instruction::LShr { dst, rhs, .. }
+        | Instruction::AShr { dst, rhs, .. } = instr
+            && let Some(scratch) = shift_amount_scratch(dst, rhs)
+        {
+            type_map.insert(&scratch, PTXType::S32);
+        }
+        if let Instruction::Trunc { dst, src, .. } = instr
+            && let Some(scratch) = truncation_scratch(CastKind::Trunc, dst)
+            && let Some(ty) = PTXType::from_ir(src.ty())
+        {
+            type_map.insert(&scratch, ty);
+        }
+        if let Instruction::AtomicRmw { op, value, .. } = instr
+            && let Some(scratch) = neg_scratch(*op, value)
+            && let Some(ty) = PTXType::from_ir(value.ty())
+        {
+            type_map.insert(&scratch, ty);
+        }
+        if let Instruction::Switch {
+            cond,
+            default,
+            cases,
+            ..
+        } = instr
+            && let Some((pred, index)) = switch_scratch(cond)
+        {
+            type_map.insert(&pred, PTXType::Pred);
+            if jump_table(cond, default, cases).is_some_and(|table| table.low != 0) {
+                type_map.insert(&index, PTXType::S32);
+            } else if condition_mask(cond).is_some()
+                && let Some(ty) = PTXType::from_ir(cond.ty())
+            {
+                type_map.insert(&index, ty);
+            }
+        }
+        if let Instruction::Call {
+            callee,
+            args,
+            byval,
+            ret,
+            ..
+        } = instr
+        {
+            let scratch = match callee {
+                Callee::Direct(name) if is_printf(name) => {
+                    printf_buffer = printf_buffer.max(buffer_size(name, args, layout));
+                    printf_scratch(name, args)
+                }
+                _ => call_scratch(args, byval, ret.as_ref(), layout),
+            };
+            for (scratch, ty) in scratch {
+                type_map.insert(&scratch, ty);
             }
         }
+        if let Instruction::Load { dst: value, .. } | Instruction::Store { value, .. } = instr
+            && let Some(scratch) = memory_scratch(value)
+        {
+            type_map.insert(&scratch, PTXType::S16);
+        }
+        if let Instruction::Ret {
+            value: Some(value), ..
+        } = instr
+            && let Some(scratch) = return_scratch(value)
+        {
+            type_map.insert(&scratch, PTXType::S16);
+        }
+        if let Instruction::GetElementPtr {
+            dst,
+            source_ty,
+            indices,
+            ..
+        } = instr
+        {
+            let (_, terms) = gep_offset(source_ty, indices, layout);
+            if terms
+                .iter()
+                .any(|(index, stride)| needs_index_scratch(index, *stride))
+            {
+                type_map.insert(&index_scratch(dst), PTXType::S64);
+            }
+        }
+    }
+    let addresses = address_uses(flat_instrs.iter().copied(), &spaces);
+    for value in &addresses {
+        if let Value::Global { name, ty } = value {
+            type_map.insert(&global_address_reg(name, ty), PTXType::Ptr);
+        }
+    }
+    let mut prototypes = vec![];
+    for instr in &flat_instrs {
+        if let Instruction::Call {
+            callee: Callee::Indirect(_),
+            args,
+            byval,
+            ret,
+            ..
+        } = instr
+            && let Ok(prototype) = prototype(args, byval, ret.as_ref(), layout)
+            && !prototypes.contains(&prototype)
+        {
+            prototypes.push(prototype);
+        }
     }
-    for line in declare_registers_from_typemap(&type_map) {
-        output.push(line);
+    let mut frame = Frame::new(&flat_instrs, layout);
+    frame.retype(&mut type_map);
+    if kind == FunctionKind::Entry {
+        for (i, param) in params.iter().enumerate() {
+            if let Some(ty) = signature.byval(i)
+                && spaces.of(param) == AddressSpace::Generic
+            {
+                frame.copy_byval(param, ty, layout);
+            }
+        }
     }
+    let param_loads = load_params(name, signature, &frame, &spaces, &mut type_map, layout);
 
-    for (block_name, instrs) in all_instrs {
+    output.extend(frame.depot().map(Statement::Var));
+    if printf_buffer > 0 {
+        output.push(Statement::Var(printf::buffer_decl(printf_buffer)));
+    }
+    for (ty, regs) in group_registers(&type_map) {
+        output.push(Statement::Regs(ty, regs));
+    }
+    output.extend(prototypes.into_iter().map(Statement::Prototype));
+    output.extend(param_loads);
+    output.extend(load_addresses(&addresses, &spaces));
+
+    // A device function may itself be called from divergent code, so only
+    // the blocks of a kernel are known to be uniform.
+    let uniform = match kind {
+        FunctionKind::Entry => uniform_blocks(all_instrs),
+        FunctionKind::Func => vec![false; all_instrs.len()],
+    };
+    for (
+        BasicBlock {
+            name: block_name,
+            instrs,
+        },
+        &uniform,
+    ) in all_instrs.iter().zip(&uniform)
+    {
         if instrs.is_empty() {
             continue;
         }
-        output.push(format!("{}:", clean_operand(block_name)));
+        output.push(Statement::Label(label_name(block_name)));
         for instr in instrs {
-            let line = to_ptx(instr, &type_map);
-            output.push(format!("    {}", line));
+            let returned = match (instr, &ret) {
+                (
+                    Instruction::Ret {
+                        value: Some(value), ..
+                    },
+                    Some(slot),
+                ) => store_return(slot, value),
+                _ => Ok(vec![]),
+            };
+            let lowered = returned.and_then(|mut statements| {
+                match frame.lower(instr, &spaces)? {
+                    Some(lowered) => statements.extend(lowered),
+                    None => statements.extend(lower_instruction(
+                        instr, block_name, uniform, &type_map, &spaces, layout, options,
+                    )?),
+                }
+                Ok(statements)
+            });
+            match lowered {
+                Ok(statements) => output.extend(statements),
+                Err(e) if options.permissive => output.push(Statement::Comment(e.summary())),
+                Err(e) => return Err(e.in_function(name).in_block(block_name).at(instr)),
+            }
         }
     }
 
@@ -74,146 +308,565 @@ pub fn lower_function(
 
     if let Some(instr) = last_instr {
         match instr {
-            Instruction::Ret { .. } | Instruction::Br { .. } => {}
-            _ => output.push("    ret;".into()),
+            Instruction::Ret { .. }
+            | Instruction::Br { .. }
+            | Instruction::Switch { .. }
+            | Instruction::Unreachable => {}
+            _ => output.push(ast::Instruction::new("ret", &[], vec![]).into()),
         }
     } else {
-        output.push("    ret;".into());
+        output.push(ast::Instruction::new("ret", &[], vec![]).into());
     }
 
-    output.push("}".into());
-    output
+    let mut callees = direct_callees(flat_instrs.iter().copied());
+    for function in addresses.iter().filter_map(|v| v.function_address()) {
+        if !callees.iter().any(|c| c == function) {
+            callees.push(function.to_string());
+        }
+    }
+
+    Ok(PtxFunction {
+        name: clean_operand(name),
+        kind,
+        linkage: match function.internal {
+            true => Linkage::Internal,
+            false => Linkage::Visible,
+        },
+        ret,
+        params: param_decls,
+        body: output,
+        callees,
+    })
 }
 
-fn emit_header(target: &str) -> String {
-    format!(".version 7.0\n.target {}\n.address_size 64\n", target)
+/// Names of the functions `instrs` call directly, in order of first call.
+/// `printf` counts as the `vprintf` it is lowered to.
+fn direct_callees<'a>(instrs: impl IntoIterator<Item = &'a Instruction>) -> Vec<String> {
+    let mut callees: Vec<String> = vec![];
+    for instr in instrs {
+        if let Instruction::Call {
+            callee: Callee::Direct(callee),
+            ..
+        } = instr
+        {
+            let callee = match is_printf(callee) {
+                true => VPRINTF,
+                false => callee,
+            };
+            if !callees.iter().any(|c| c == callee) {
+                callees.push(callee.to_string());
+            }
+        }
+    }
+    callees
 }
 
-pub fn to_ptx(instr: &Instruction, type_map: &TypeMap) -> String {
-    use Instruction::*;
+/// Name of the `.param` variable holding the `index`-th kernel argument.
+fn param_name(function: &str, index: usize) -> String {
+    format!("{}_param_{}", clean_operand(function), index)
+}
 
-    fn reg(op: &str) -> String {
-        let clean = clean_operand(op);
-        if clean.starts_with('%') {
-            clean
-        } else {
-            format!("%{}", clean)
-        }
+/// Declare the `.param` variable of every argument; aggregates passed
+/// `byval` get an aligned byte array.
+fn declare_params(
+    name: &str,
+    signature: &Signature,
+    layout: &DataLayout,
+) -> Result<Vec<VarDecl>, CodegenError> {
+    signature
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            if let Some(ty) = signature.byval(i) {
+                return Ok(aggregate_param(&param_name(name, i), ty, layout));
+            }
+            let ty = PTXType::param_type(param.ty()).ok_or_else(|| {
+                CodegenError::new(format!(
+                    "unsupported type {:?} for parameter {}",
+                    param.ty(),
+                    i
+                ))
+                .in_function(name)
+            })?;
+            Ok(VarDecl::param(ty, &param_name(name, i)))
+        })
+        .collect()
+}
+
+/// Declare the `.param` return slot of a device function.
+fn declare_return(
+    name: &str,
+    kind: FunctionKind,
+    ret_ty: &Type,
+) -> Result<Option<VarDecl>, CodegenError> {
+    if kind == FunctionKind::Entry || *ret_ty == Type::Void {
+        return Ok(None);
     }
+    let ty = PTXType::param_type(ret_ty).ok_or_else(|| {
+        CodegenError::new(format!("unsupported return type {:?}", ret_ty)).in_function(name)
+    })?;
+    Ok(Some(VarDecl::param(ty, "func_retval0")))
+}
+
+/// Scratch register moving an `i1` register value to or from its byte
+/// in memory.
+fn memory_scratch(value: &Value) -> Option<String> {
+    let name = value.local_name()?;
+    (*value.ty() == Type::Int(1)).then(|| format!("{}$mem", clean_operand(name)))
+}
 
-    fn mem(op: &str) -> String {
-        let clean = clean_operand(op);
-        if clean.starts_with('%') {
-            format!("[{}]", clean)
-        } else {
-            format!("[%{}]", clean)
+/// Scratch register through which an `i1` register is returned, since
+/// predicates cannot be stored.
+fn return_scratch(value: &Value) -> Option<String> {
+    let name = value.local_name()?;
+    (*value.ty() == Type::Int(1)).then(|| format!("{}$ret", clean_operand(name)))
+}
+
+/// Store the returned value into the `.param` return slot, where the
+/// caller's `ld.param` picks it up after the call.
+fn store_return(slot: &VarDecl, value: &Value) -> Result<Vec<Statement>, CodegenError> {
+    let ty = slot.ty.as_str();
+    let dst = Operand::Symbol(slot.name.clone()).addr();
+    let mut statements = vec![];
+    let src = match return_scratch(value) {
+        Some(scratch) => {
+            let scratch = Operand::Reg(scratch);
+            statements.push(
+                ast::Instruction::new(
+                    "selp",
+                    &["u16"],
+                    vec![
+                        scratch.clone(),
+                        Operand::Imm(1),
+                        Operand::Imm(0),
+                        Operand::from_value(value)?,
+                    ],
+                )
+                .into(),
+            );
+            scratch
         }
-    }
+        None => Operand::from_value(value)?,
+    };
+    statements.push(ast::Instruction::new("st", &["param", ty], vec![dst, src]).into());
+    Ok(statements)
+}
+
+/// Load every argument from the parameter space into its register.
+///
+/// Kernel pointer arguments only used as global addresses are converted with
+/// `cvta.to.global`, since the host always passes global memory. `i1`
+/// arguments travel as bytes and are turned into predicates through a
+/// 16-bit scratch register. A `byval` argument is not loaded: its register
+/// takes the address of the `.param` variable instead, or of its copy in
+/// the depot if the function is a kernel and the address escapes.
+fn load_params(
+    name: &str,
+    signature: &Signature,
+    frame: &Frame,
+    spaces: &AddressSpaces,
+    type_map: &mut TypeMap,
+    layout: &DataLayout,
+) -> Vec<Statement> {
+    let mut statements = vec![];
 
-    fn extract_arg_name(arg: &str) -> String {
-        if arg.starts_with("i32 ") || arg.starts_with("f32 ") {
-            arg.split_whitespace().last().unwrap_or(arg).to_string()
-        } else {
-            arg.to_string()
+    for (i, param) in signature.params.iter().enumerate() {
+        let mut push = |instr: ast::Instruction| statements.push(Statement::Instr(instr));
+        if let Some(ty) = signature.byval(i)
+            && let Some(reg_name) = param.local_name()
+        {
+            let reg = Operand::reg(reg_name);
+            let symbol = Operand::Symbol(param_name(name, i));
+            match (frame.byval_copy(param), byval_scratch(param)) {
+                (Some(offset), Some(scratch)) => {
+                    depot_address(&reg, offset).into_iter().for_each(push);
+                    type_map.insert(&scratch, copy_unit(ty, layout).1);
+                    statements.extend(copy_bytes(
+                        &scratch,
+                        ty,
+                        (AddressSpace::Param, &symbol),
+                        (AddressSpace::Local, &reg),
+                        layout,
+                    ));
+                }
+                _ => push(ast::Instruction::new(
+                    "mov",
+                    &["u64"],
+                    vec![reg.clone(), symbol],
+                )),
+            }
+            if spaces.of(param) == AddressSpace::Generic {
+                statements.push(
+                    ast::Instruction::new("cvta", &["local", "u64"], vec![reg.clone(), reg]).into(),
+                );
+            }
+            continue;
+        }
+        let Some(param_ty) = PTXType::param_type(param.ty()) else {
+            continue;
+        };
+        let Some(reg_name) = param.local_name().map(clean_operand) else {
+            continue;
+        };
+        let reg = Operand::Reg(reg_name.clone());
+        let slot = Operand::Symbol(param_name(name, i)).addr();
+
+        match param.ty() {
+            ir_model::Type::Int(1) => {
+                let scratch = format!("{reg_name}$param");
+                type_map.insert(&scratch, PTXType::S16);
+                let scratch = Operand::Reg(scratch);
+                push(ast::Instruction::new(
+                    "ld",
+                    &["param", param_ty],
+                    vec![scratch.clone(), slot],
+                ));
+                push(ast::Instruction::new(
+                    "setp",
+                    &["ne", "s16"],
+                    vec![reg, scratch, Operand::Imm(0)],
+                ));
+            }
+            ir_model::Type::Ptr { .. } if spaces.of(param) == AddressSpace::Global => {
+                push(ast::Instruction::new(
+                    "ld",
+                    &["param", param_ty],
+                    vec![reg.clone(), slot],
+                ));
+                push(ast::Instruction::new(
+                    "cvta",
+                    &["to", "global", "u64"],
+                    vec![reg.clone(), reg],
+                ));
+            }
+            _ => push(ast::Instruction::new(
+                "ld",
+                &["param", param_ty],
+                vec![reg, slot],
+            )),
         }
     }
 
-    match instr {
-        FMul { dst, lhs, rhs, .. } => {
-            format!("mul.f32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+    statements
+}
+
+/// Lower one IR instruction to PTX text, one instruction per line.
+/// Instructions that cannot be lowered become a comment saying why.
+pub fn to_ptx(
+    instr: &Instruction,
+    type_map: &TypeMap,
+    spaces: &AddressSpaces,
+    layout: &DataLayout,
+) -> String {
+    let options = CodegenOptions::default();
+    let statements = lower_instruction(instr, "", false, type_map, spaces, layout, &options)
+        .unwrap_or_else(|e| vec![Statement::Comment(e.summary())]);
+    let text = print_statements(&statements, 1).join("\n");
+    text.strip_prefix("    ").unwrap_or(&text).to_string()
+}
+
+/// Lower one IR instruction of block `block` to PTX statements. `uniform`
+/// tells whether every thread of a warp executes the block together.
+pub fn lower_instruction(
+    instr: &Instruction,
+    block: &str,
+    uniform: bool,
+    type_map: &TypeMap,
+    spaces: &AddressSpaces,
+    layout: &DataLayout,
+    options: &CodegenOptions,
+) -> Result<Vec<Statement>, CodegenError> {
+    use Instruction::*;
+
+    fn reg(op: &Value) -> Result<Operand, CodegenError> {
+        Operand::from_value(op)
+    }
+
+    /// State space and memory operand of a load or store address.
+    /// Variables are addressed by their symbol.
+    fn mem(ptr: &Value, spaces: &AddressSpaces) -> Result<(AddressSpace, Operand), CodegenError> {
+        Ok(match (spaces.variable(ptr), ptr.name()) {
+            (Some(space), Some(name)) => (space, Operand::symbol(name).addr()),
+            _ => (spaces.of(ptr), Operand::from_value(ptr)?.addr()),
+        })
+    }
+
+    fn reg_type(type_map: &TypeMap, v: &Value) -> Option<PTXType> {
+        v.name()
+            .and_then(|n| type_map.get(&clean_operand(n)).copied())
+            .or_else(|| PTXType::from_ir(v.ty()))
+    }
+
+    fn ty_of<'a>(type_map: &'a TypeMap, v: &Value) -> &'a str {
+        reg_type(type_map, v).unwrap_or(PTXType::S32).as_str()
+    }
+
+    /// Type of a load or store of `v`: the register type, except for
+    /// values narrower than their register, accessed at their own width.
+    fn access_type<'a>(type_map: &'a TypeMap, v: &Value) -> &'a str {
+        match PTXType::memory_from_ir(v.ty()) {
+            Some(ty @ (PTXType::S8 | PTXType::U8)) => ty.as_str(),
+            _ => ty_of(type_map, v),
         }
-        FAdd { dst, lhs, rhs, .. } => {
-            format!("add.f32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+    }
+
+    /// `s*`/`u*` type of integer arithmetic on `v`, following its register.
+    fn int_type(type_map: &TypeMap, v: &Value, signed: bool) -> Result<&'static str, CodegenError> {
+        reg_type(type_map, v)
+            .and_then(|ty| ty.as_int(signed))
+            .ok_or_else(|| CodegenError::new(format!("unsupported integer type: {:?}", v.ty())))
+    }
+
+    /// `f16`/`f32`/`f64` type of floating-point arithmetic on `v`.
+    fn float_type(type_map: &TypeMap, v: &Value) -> Result<&'static str, CodegenError> {
+        match reg_type(type_map, v) {
+            Some(ty @ (PTXType::F16 | PTXType::F32 | PTXType::F64)) => Ok(ty.as_str()),
+            _ => Err(CodegenError::new(format!(
+                "unsupported floating-point type: {:?}",
+                v.ty()
+            ))),
         }
+    }
+
+    fn binary(
+        opcode: &str,
+        modifiers: &[&str],
+        dst: &Value,
+        lhs: &Value,
+        rhs: &Value,
+    ) -> Result<Vec<Statement>, CodegenError> {
+        Ok(vec![
+            ast::Instruction::new(opcode, modifiers, vec![reg(dst)?, reg(lhs)?, reg(rhs)?]).into(),
+        ])
+    }
+
+    /// `b16`/`b32`/`b64`, or `pred` for `i1`.
+    fn bit_type(dst: &Value) -> Result<&'static str, CodegenError> {
+        PTXType::from_ir(dst.ty())
+            .filter(|_| dst.ty().is_integer())
+            .map(|ty| ty.as_bits())
+            .ok_or_else(|| CodegenError::new(format!("unsupported bitwise type: {:?}", dst.ty())))
+    }
+
+    fn logic(
+        opcode: &str,
+        dst: &Value,
+        lhs: &Value,
+        rhs: &Value,
+    ) -> Result<Vec<Statement>, CodegenError> {
+        binary(opcode, &[bit_type(dst)?], dst, lhs, rhs)
+    }
+
+    /// `shl.b*`, or `shr.u*`/`shr.s*` for `signedness` `u`/`s`. The shift
+    /// amount is always a 32-bit operand in PTX.
+    fn shift(
+        opcode: &str,
+        signedness: &str,
+        dst: &Value,
+        lhs: &Value,
+        rhs: &Value,
+    ) -> Result<Vec<Statement>, CodegenError> {
+        // Narrow integers live in wider registers with unspecified high
+        // bits, which only a left shift can ignore.
+        let exact = matches!(dst.ty().int_bits(), Some(16 | 32 | 64));
+        let ty = match PTXType::from_ir(dst.ty()) {
+            Some(PTXType::S16 | PTXType::S32 | PTXType::S64) if exact || signedness == "b" => {
+                format!("{}{}", signedness, &bit_type(dst)?[1..])
+            }
+            _ => {
+                return Err(CodegenError::new(format!(
+                    "unsupported shift type: {:?}",
+                    dst.ty()
+                )));
+            }
+        };
+
+        let mut statements = vec![];
+        let amount = match shift_amount_scratch(dst, rhs) {
+            Some(scratch) => {
+                let from = match PTXType::from_ir(rhs.ty()) {
+                    Some(PTXType::S16) => "u16",
+                    _ => "u64",
+                };
+                statements.push(
+                    ast::Instruction::new(
+                        "cvt",
+                        &["u32", from],
+                        vec![Operand::Reg(scratch.clone()), reg(rhs)?],
+                    )
+                    .into(),
+                );
+                Operand::Reg(scratch)
+            }
+            None => reg(rhs)?,
+        };
+        statements
+            .push(ast::Instruction::new(opcode, &[&ty], vec![reg(dst)?, reg(lhs)?, amount]).into());
+        Ok(statements)
+    }
+
+    fn cast(kind: CastKind, dst: &Value, src: &Value) -> Result<Vec<Statement>, CodegenError> {
+        Ok(lower_cast(kind, dst, src)?
+            .into_iter()
+            .map(Statement::from)
+            .collect())
+    }
+
+    let statements = match instr {
+        FMul { dst, lhs, rhs, .. } => binary("mul", &[float_type(type_map, dst)?], dst, lhs, rhs)?,
+        FAdd { dst, lhs, rhs, .. } => binary("add", &[float_type(type_map, dst)?], dst, lhs, rhs)?,
         Add { dst, lhs, rhs, .. } => {
-            format!("add.s32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+            binary("add", &[int_type(type_map, dst, true)?], dst, lhs, rhs)?
         }
         Sub { dst, lhs, rhs, .. } => {
-            format!("sub.s32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
-        }
-        FSub { dst, lhs, rhs, .. } => {
-            format!("sub.f32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
-        }
-        Mul { dst, lhs, rhs, .. } => {
-            format!("mul.lo.s32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+            binary("sub", &[int_type(type_map, dst, true)?], dst, lhs, rhs)?
         }
+        FSub { dst, lhs, rhs, .. } => binary("sub", &[float_type(type_map, dst)?], dst, lhs, rhs)?,
+        Mul { dst, lhs, rhs, .. } => binary(
+            "mul",
+            &["lo", int_type(type_map, dst, true)?],
+            dst,
+            lhs,
+            rhs,
+        )?,
         UDiv { dst, lhs, rhs, .. } => {
-            format!("div.u32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+            binary("div", &[int_type(type_map, dst, false)?], dst, lhs, rhs)?
         }
         SDiv { dst, lhs, rhs, .. } => {
-            format!("div.s32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+            binary("div", &[int_type(type_map, dst, true)?], dst, lhs, rhs)?
         }
         URem { dst, lhs, rhs, .. } => {
-            format!("rem.u32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+            binary("rem", &[int_type(type_map, dst, false)?], dst, lhs, rhs)?
         }
         SRem { dst, lhs, rhs, .. } => {
-            format!("rem.s32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
-        }
-        FDiv { dst, lhs, rhs, .. } => {
-            format!("div.f32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
-        }
-        FRem { dst, lhs, rhs, .. } => {
-            format!("rem.f32 {}, {}, {};", reg(dst), reg(lhs), reg(rhs))
+            binary("rem", &[int_type(type_map, dst, true)?], dst, lhs, rhs)?
         }
+        FDiv { dst, lhs, rhs, .. } => binary("div", &[float_type(type_map, dst)?], dst, lhs, rhs)?,
+        FRem { dst, lhs, rhs, .. } => binary("rem", &[float_type(type_map, dst)?], dst, lhs, rhs)?,
+        And { dst, lhs, rhs, .. } => logic("and", dst, lhs, rhs)?,
+        Or { dst, lhs, rhs, .. } => logic("or", dst, lhs, rhs)?,
+        // `xor` with all ones is how LLVM spells `not`.
+        Xor { dst, lhs, rhs, .. } => match (is_all_ones(lhs), is_all_ones(rhs)) {
+            (_, true) | (true, _) => {
+                let src = if is_all_ones(rhs) { lhs } else { rhs };
+                vec![
+                    ast::Instruction::new("not", &[bit_type(dst)?], vec![reg(dst)?, reg(src)?])
+                        .into(),
+                ]
+            }
+            _ => logic("xor", dst, lhs, rhs)?,
+        },
+        Shl { dst, lhs, rhs, .. } => shift("shl", "b", dst, lhs, rhs)?,
+        LShr { dst, lhs, rhs, .. } => shift("shr", "u", dst, lhs, rhs)?,
+        AShr { dst, lhs, rhs, .. } => shift("shr", "s", dst, lhs, rhs)?,
         ICmp {
             dst, lhs, rhs, op, ..
         } => {
-            let pred = match op.as_str() {
-                "EQ" => "eq",
-                "NE" => "ne",
-                "UGT" | "SGT" => "gt",
-                "UGE" | "SGE" => "ge",
-                "ULT" | "SLT" => "lt",
-                "ULE" | "SLE" => "le",
+            // Unsigned predicates compare as `u*`, under their PTX names.
+            let (pred, signed) = match op.as_str() {
+                "EQ" => ("eq", true),
+                "NE" => ("ne", true),
+                "SGT" => ("gt", true),
+                "SGE" => ("ge", true),
+                "SLT" => ("lt", true),
+                "SLE" => ("le", true),
+                "UGT" => ("hi", false),
+                "UGE" => ("hs", false),
+                "ULT" => ("lo", false),
+                "ULE" => ("ls", false),
                 _ => {
-                    return format!("// unsupported icmp predicate: {}", op);
+                    return Err(CodegenError::new(format!(
+                        "unsupported icmp predicate: {}",
+                        op
+                    )));
                 }
             };
-            format!(
-                "setp.{}.s32 {}, {}, {};",
-                pred,
-                reg(dst),
-                reg(lhs),
-                reg(rhs)
-            )
+            binary(
+                "setp",
+                &[pred, int_type(type_map, lhs, signed)?],
+                dst,
+                lhs,
+                rhs,
+            )?
         }
         FCmp {
             dst, lhs, rhs, op, ..
         } => {
             let pred = match op.as_str() {
-                "OEQ" | "UEQ" => "eq",
-                "ONE" | "UNE" => "ne",
-                "OGT" | "UGT" => "gt",
-                "OGE" | "UGE" => "ge",
-                "OLT" | "ULT" => "lt",
-                "OLE" | "ULE" => "le",
-                _ => "lt", // fallback
+                "OEQ" => "eq",
+                "ONE" => "ne",
+                "OGT" => "gt",
+                "OGE" => "ge",
+                "OLT" => "lt",
+                "OLE" => "le",
+                "UEQ" => "equ",
+                "UNE" => "neu",
+                "UGT" => "gtu",
+                "UGE" => "geu",
+                "ULT" => "ltu",
+                "ULE" => "leu",
+                "ORD" => "num",
+                "UNO" => "nan",
+                _ => {
+                    return Err(CodegenError::new(format!(
+                        "unsupported fcmp predicate: {}",
+                        op
+                    )));
+                }
             };
-            format!(
-                "setp.{}.f32 {}, {}, {};",
-                pred,
-                reg(dst),
-                reg(lhs),
-                reg(rhs)
-            )
+            binary("setp", &[pred, float_type(type_map, lhs)?], dst, lhs, rhs)?
         }
+        // An `i1` is a byte in memory, read into and written from a 16-bit
+        // scratch register since predicates cannot be loaded or stored.
         Load { dst, src, .. } => {
-            let ty = type_map
-                .get(&clean_operand(dst))
-                .unwrap_or(&PTXType::S32)
-                .as_str();
-
-            let space = "global";
-            format!("ld.{space}.{ty} {}, [{}];", reg(dst), clean_operand(src))
+            let ty = access_type(type_map, dst);
+            let (space, addr) = mem(src, spaces)?;
+            let modifiers = state_space(space, ty);
+            match memory_scratch(dst) {
+                Some(scratch) => {
+                    let scratch = Operand::Reg(scratch);
+                    vec![
+                        ast::Instruction::new("ld", &modifiers, vec![scratch.clone(), addr]).into(),
+                        ast::Instruction::new(
+                            "setp",
+                            &["ne", "u16"],
+                            vec![reg(dst)?, scratch, Operand::Imm(0)],
+                        )
+                        .into(),
+                    ]
+                }
+                None => {
+                    vec![ast::Instruction::new("ld", &modifiers, vec![reg(dst)?, addr]).into()]
+                }
+            }
         }
         Store { dst, value, .. } => {
-            let ty = type_map
-                .get(&clean_operand(value))
-                .unwrap_or(&PTXType::S32)
-                .as_str();
-
-            let space = "global";
-            format!("st.{space}.{ty} {}, {};", mem(dst), reg(value))
+            let ty = access_type(type_map, value);
+            let (space, addr) = mem(dst, spaces)?;
+            let modifiers = state_space(space, ty);
+            match memory_scratch(value) {
+                Some(scratch) => {
+                    let scratch = Operand::Reg(scratch);
+                    vec![
+                        ast::Instruction::new(
+                            "selp",
+                            &["u16"],
+                            vec![
+                                scratch.clone(),
+                                Operand::Imm(1),
+                                Operand::Imm(0),
+                                reg(value)?,
+                            ],
+                        )
+                        .into(),
+                        ast::Instruction::new("st", &modifiers, vec![addr, scratch]).into(),
+                    ]
+                }
+                None => {
+                    vec![ast::Instruction::new("st", &modifiers, vec![addr, reg(value)?]).into()]
+                }
+            }
         }
         Br {
             cond,
@@ -221,42 +874,166 @@ pub fn to_ptx(instr: &Instruction, type_map: &TypeMap) -> String {
             target_false,
             ..
         } => match (cond, target_false) {
-            (Some(c), Some(f)) => format!("@{} bra {};\n    bra {};", reg(c), target_true, f),
-            (None, _) => format!("bra {};", target_true),
-            _ => "// invalid conditional branch".to_string(),
+            (Some(c), Some(f)) => branch(Some(c), target_true, Some(f))?,
+            (None, _) => branch(None, target_true, None)?,
+            _ => return Err(CodegenError::new("invalid conditional branch")),
         },
         CondBr {
             cond,
             then_target,
             else_target,
             ..
-        } => {
-            format!(
-                "@{cond} bra {then};\n    bra {els};",
-                cond = reg(cond),
-                then = then_target,
-                els = else_target
-            )
-        }
-        Ret { .. } => "ret;".to_string(),
+        } => branch(Some(cond), then_target, Some(else_target))?,
+        Ret { .. } => vec![ast::Instruction::new("ret", &[], vec![]).into()],
+        Switch {
+            cond,
+            default,
+            cases,
+            ..
+        } => lower_switch(cond, default, cases, block)?,
+        Unreachable => {
+            let opcode = match options.unreachable {
+                OnUnreachable::Trap => "trap",
+                OnUnreachable::Exit => "exit",
+            };
+            vec![ast::Instruction::new(opcode, &[], vec![]).into()]
+        }
         GetElementPtr {
-            dst, base, index, ..
+            dst,
+            base,
+            source_ty,
+            indices,
+            ..
+        } => lower_gep(dst, base, source_ty, indices, layout)?
+            .into_iter()
+            .map(Statement::from)
+            .collect(),
+        Phi { dst, incoming, .. } => {
+            let ty = ty_of(type_map, dst);
+            let incoming = incoming
+                .iter()
+                .map(|(label, val)| Ok(format!("{}: {}", label_name(label), reg(val)?)))
+                .collect::<Result<Vec<_>, CodegenError>>()?
+                .join(", ");
+            vec![Statement::Comment(format!(
+                "phi.{ty} {} <- [{}]",
+                reg(dst)?,
+                incoming
+            ))]
+        }
+        Copy { dst, src, .. } => {
+            let ty = match ty_of(type_map, dst) {
+                "f16" => "b16",
+                ty => ty,
+            };
+            vec![ast::Instruction::new("mov", &[ty], vec![reg(dst)?, reg(src)?]).into()]
+        }
+        Alloca { .. } => vec![],
+        AtomicRmw {
+            dst,
+            op,
+            ptr,
+            value,
+            ordering,
+            scope,
+            ..
+        } => lower_atomic_rmw(
+            dst.as_ref(),
+            *op,
+            ptr,
+            value,
+            (*ordering, *scope),
+            spaces.of(ptr),
+            options,
+        )?
+        .into_iter()
+        .map(Statement::from)
+        .collect(),
+        CmpXchg {
+            dst,
+            ptr,
+            expected,
+            new,
+            ordering,
+            scope,
+            ..
+        } => lower_cmpxchg(
+            dst,
+            ptr,
+            expected,
+            new,
+            (*ordering, *scope),
+            spaces.of(ptr),
+            options,
+        )?
+        .into_iter()
+        .map(Statement::from)
+        .collect(),
+        ExtractValue {
+            dst,
+            aggregate,
+            indices,
+            ..
         } => {
-            let base_clean = clean_operand(base);
-            let dst_clean = clean_operand(dst);
-            let offset = format!("{}_offset", dst_clean);
-            let calc_offset = format!("mul.lo.s32 %{}, %{}, 4;", offset, clean_operand(index));
-            let calc_ptr = format!("add.s32 %{}, %{}, %{};", dst_clean, base_clean, offset);
-            format!("{calc_offset}\n    {calc_ptr}")
+            // Only the `{ old, success }` pair of a `cmpxchg` is kept in
+            // registers; other aggregates have no register form.
+            let (old, ok) = cas_scratch(aggregate);
+            let src = match indices.as_slice() {
+                [0] if type_map.get(&old).is_some() => old,
+                [1] if type_map.get(&ok).is_some() => ok,
+                _ => {
+                    return Err(CodegenError::new(format!(
+                        "unsupported extractvalue {:?} of {}",
+                        indices,
+                        reg(aggregate)?
+                    )));
+                }
+            };
+            let ty = ty_of(type_map, dst);
+            vec![ast::Instruction::new("mov", &[ty], vec![reg(dst)?, Operand::Reg(src)]).into()]
         }
-        Phi { dst, incoming, .. } => {
-            let ty = type_map
-                .get(&clean_operand(dst))
-                .unwrap_or(&PTXType::S32)
-                .as_str();
-            format!("// phi.{ty} {} <- {:?}", reg(dst), incoming)
+        Barrier {
+            kind,
+            aligned,
+            id,
+            count,
+            ..
+        } => {
+            let opcode = if *aligned { "bar" } else { "barrier" };
+            let action = match kind {
+                BarrierKind::Sync => "sync",
+                BarrierKind::Arrive => "arrive",
+            };
+            let mut operands = vec![reg(id)?];
+            operands.extend(count.iter().map(reg).collect::<Result<Vec<_>, _>>()?);
+            vec![ast::Instruction::new(opcode, &[action], operands).into()]
+        }
+        MemBar { scope, .. } => {
+            vec![ast::Instruction::new("membar", &[membar_level(*scope)], vec![]).into()]
+        }
+        Fence {
+            ordering, scope, ..
+        } => {
+            // PTX fences are either sequentially consistent or acquire-release;
+            // the weaker LLVM orderings round up to the latter.
+            let sem = match ordering {
+                MemoryOrdering::SeqCst => "sc",
+                _ => "acq_rel",
+            };
+            vec![ast::Instruction::new("fence", &[sem, scope_name(*scope)], vec![]).into()]
+        }
+        ReadSpecialReg { dst, reg: sreg, .. } => {
+            let ty = match dst.ty().int_bits() {
+                Some(64) => "u64",
+                _ => "u32",
+            };
+            let src = match sreg {
+                // Not a register: the warp size is an assembler constant.
+                SpecialRegister::WarpSize => Operand::Symbol("WARP_SZ".into()),
+                _ => Operand::Symbol(format!("%{}", sreg.name())),
+            };
+            vec![ast::Instruction::new("mov", &[ty], vec![reg(dst)?, src]).into()]
         }
-        Alloca { .. } => String::new(),
         Select {
             dst,
             cond,
@@ -264,129 +1041,239 @@ pub fn to_ptx(instr: &Instruction, type_map: &TypeMap) -> String {
             val_false,
             ..
         } => {
-            let ty = type_map
-                .get(&clean_operand(dst))
-                .unwrap_or(&PTXType::S32)
-                .as_str();
-            format!(
-                "selp.{ty} {}, {}, {}, {};",
-                reg(dst),
-                reg(val_true),
-                reg(val_false),
-                reg(cond)
-            )
-        }
-        Bitcast { dst, src, .. } => {
-            format!("mov.b32 {}, {};", reg(dst), reg(src))
-        }
-        ZExt { dst, src, .. } => {
-            format!("cvt.u32.u8 {}, {};", reg(dst), reg(src))
-        }
-        Trunc { dst, src, .. } => {
-            format!("cvt.u8.u32 {}, {};", reg(dst), reg(src))
+            let ty = ty_of(type_map, dst);
+            vec![
+                ast::Instruction::new(
+                    "selp",
+                    &[ty],
+                    vec![reg(dst)?, reg(val_true)?, reg(val_false)?, reg(cond)?],
+                )
+                .into(),
+            ]
         }
-        Call {
-            callee, args, ret, ..
-        } => {
-            let mut ptx = String::new();
-
-            if let Some(retvar) = ret {
-                let ty = type_map
-                    .get(&clean_operand(retvar))
-                    .unwrap_or(&PTXType::S32)
-                    .as_str();
-                ptx.push_str(&format!("\t.param .{ty} retval_{};\n", retvar));
+        ZExt { dst, src, .. } => cast(CastKind::ZExt, dst, src)?,
+        SExt { dst, src, .. } => cast(CastKind::SExt, dst, src)?,
+        Trunc { dst, src, .. } => cast(CastKind::Trunc, dst, src)?,
+        FPToSI { dst, src, .. } => cast(CastKind::FPToSI, dst, src)?,
+        FPToUI { dst, src, .. } => cast(CastKind::FPToUI, dst, src)?,
+        SIToFP { dst, src, .. } => cast(CastKind::SIToFP, dst, src)?,
+        UIToFP { dst, src, .. } => cast(CastKind::UIToFP, dst, src)?,
+        FPExt { dst, src, .. } => cast(CastKind::FPExt, dst, src)?,
+        FPTrunc { dst, src, .. } => cast(CastKind::FPTrunc, dst, src)?,
+        PtrToInt { dst, src, .. } => cast(CastKind::PtrToInt, dst, src)?,
+        IntToPtr { dst, src, .. } => cast(CastKind::IntToPtr, dst, src)?,
+        Bitcast { dst, src, .. } => cast(CastKind::Bitcast, dst, src)?,
+        AddrSpaceCast { dst, src, .. } => {
+            let (from, to) = (spaces.of(src), spaces.of(dst));
+            if from == to {
+                return Ok(vec![
+                    ast::Instruction::new("mov", &["u64"], vec![reg(dst)?, reg(src)?]).into(),
+                ]);
             }
-
-            for (i, arg) in args.iter().enumerate() {
-                let arg_val = extract_arg_name(arg);
-                let ty = type_map
-                    .get(&clean_operand(&arg_val))
-                    .unwrap_or(&PTXType::S32)
-                    .as_str();
-                ptx.push_str(&format!("\t.param .{ty} arg{i};\n"));
+            let mut statements = vec![];
+            let mut addr = reg(src)?;
+            if let Some(from) = from.as_str() {
+                statements.push(
+                    ast::Instruction::new("cvta", &[from, "u64"], vec![reg(dst)?, addr]).into(),
+                );
+                addr = reg(dst)?;
             }
-
-            for (i, arg) in args.iter().enumerate() {
-                let arg_val = extract_arg_name(arg);
-                let reg_name = reg(&arg_val);
-                let ty = type_map
-                    .get(&clean_operand(&arg_val))
-                    .unwrap_or(&PTXType::S32)
-                    .as_str();
-
-                ptx.push_str(&format!("\tst.param.{ty} [arg{i}], {reg_name};\n"));
+            if let Some(to) = to.as_str() {
+                statements.push(
+                    ast::Instruction::new("cvta", &["to", to, "u64"], vec![reg(dst)?, addr]).into(),
+                );
             }
+            statements
+        }
+        Call {
+            callee: Callee::Direct(name),
+            args,
+            ret,
+            ..
+        } if is_printf(name) => lower_printf(name, args, ret.as_ref(), uniform, spaces, layout)?,
+        Call {
+            callee,
+            args,
+            byval,
+            ret,
+            ..
+        } => lower_call(callee, args, byval, ret.as_ref(), uniform, spaces, layout)?,
 
-            let arg_params = (0..args.len())
-                .map(|i| format!("arg{i}"))
-                .collect::<Vec<_>>()
-                .join(", ");
+        Unhandled { .. } => return Err(CodegenError::new("unhandled").at(instr)),
+    };
+    Ok(statements)
+}
 
-            if let Some(retvar) = ret {
-                ptx.push_str(&format!(
-                    "\tcall (retval_{retvar}) {}, ({});\n",
-                    clean_operand(callee),
-                    arg_params
-                ));
-                let ty = type_map
-                    .get(&clean_operand(retvar))
-                    .unwrap_or(&PTXType::S32)
-                    .as_str();
-                ptx.push_str(&format!(
-                    "\tld.param.{ty} {}, [retval_{}];\n",
-                    reg(retvar),
-                    retvar
-                ));
-            } else {
-                ptx.push_str(&format!(
-                    "\tcall {}, ({});\n",
-                    clean_operand(callee),
-                    arg_params
-                ));
-            }
+/// Integer constant with every bit set, at its own width.
+fn is_all_ones(value: &Value) -> bool {
+    match (value, value.ty().int_bits()) {
+        (Value::ConstInt { value, .. }, Some(bits)) if bits < 64 => *value == (1u64 << bits) - 1,
+        (Value::ConstInt { value, .. }, Some(_)) => *value == u64::MAX,
+        _ => false,
+    }
+}
 
-            ptx
+/// Scratch register for a shift amount held in a register that is not
+/// 32 bits wide, since PTX shifts take a `.u32` amount.
+fn shift_amount_scratch(dst: &Value, amount: &Value) -> Option<String> {
+    let name = dst.local_name()?;
+    match PTXType::from_ir(amount.ty()) {
+        Some(PTXType::S16 | PTXType::S64) if amount.local_name().is_some() => {
+            Some(format!("{}$amt", clean_operand(name)))
         }
+        _ => None,
+    }
+}
 
-        Unhandled { text, .. } => format!("// unhandled: {}", text),
+/// `bra` to `target`, guarded by `cond` if any, then to `fallthrough`.
+fn branch(
+    cond: Option<&Value>,
+    target: &str,
+    fallthrough: Option<&String>,
+) -> Result<Vec<Statement>, CodegenError> {
+    let jump =
+        |label: &str| ast::Instruction::new("bra", &[], vec![Operand::Symbol(label_name(label))]);
+    let mut statements = vec![];
+    match cond {
+        Some(cond) => statements.push(jump(target).guarded(&Operand::from_value(cond)?).into()),
+        None => statements.push(jump(target).into()),
     }
+    if let (Some(_), Some(f)) = (cond, fallthrough) {
+        statements.push(jump(f).into());
+    }
+    Ok(statements)
+}
+
+/// `ld`/`st` modifiers: the state space, if not generic, then the type.
+fn state_space(space: AddressSpace, ty: &str) -> Vec<&str> {
+    space.as_str().into_iter().chain([ty]).collect()
 }
 
-use anyhow::Result;
 use llvm_ir::Module;
 use llvm_parser::parse_llvm_ir_from_str;
 
+/// Compile LLVM IR text to PTX text. Lowering failures are returned as a
+/// [`CodegenError`] inside the `anyhow::Error`.
 pub fn compile_llvm_to_ptx(ir_code: &str) -> Result<String> {
     let module: Module = parse_llvm_ir_from_str(ir_code)?;
-    let mut ptx_lines = vec![];
+    let mut module = llvm_parser::lower_module(&module)?;
+    llvm_parser::recover_atomic_orderings(&mut module, ir_code)?;
+    let options = CodegenOptions {
+        kernels: llvm_parser::nvvm_kernels(ir_code),
+        ..Default::default()
+    };
+    Ok(compile_ir_module(&module, &options)?.to_string())
+}
 
-    for func in &module.functions {
-        let blocks = llvm_parser::lower(func)?;
-        let kernel_name = &func.name;
+/// Names of the functions to emit as `.entry`.
+///
+/// Kernels come from `options.kernels` (which callers fill from the CLI and
+/// `nvvm.annotations`) and the `kernel` flag, set by the `ptx_kernel`
+/// calling convention. When the module marks none, every `void` function
+/// that no other function calls or takes the address of is taken as a
+/// kernel.
+fn kernel_names(module: &ir_model::Module, options: &CodegenOptions) -> Vec<String> {
+    let marked: Vec<String> = module
+        .definitions()
+        .filter(|f| options.kernels.contains(&f.name) || f.kernel)
+        .map(|f| f.name.clone())
+        .collect();
+    if !marked.is_empty() {
+        return marked;
+    }
 
-        // Count no-supported instructions per function
-        let flat_instrs: Vec<&Instruction> = blocks
-            .iter()
-            .flat_map(|(_, instrs)| instrs.iter())
-            .collect();
+    // A function only referenced by address is a callback, not a kernel.
+    let called: Vec<String> = module
+        .definitions()
+        .flat_map(|f| {
+            let mut called = direct_callees(f.instructions());
+            called.extend(
+                address_uses(f.instructions(), &AddressSpaces::default())
+                    .into_iter()
+                    .filter_map(Value::function_address)
+                    .map(String::from),
+            );
+            called
+        })
+        .collect();
 
-        let unhandled_count = flat_instrs
-            .iter()
+    module
+        .definitions()
+        .filter(|f| f.signature.ret == Type::Void && !called.contains(&f.name))
+        .map(|f| f.name.clone())
+        .collect()
+}
+
+/// Lower every function of an LLVM module into one PTX module.
+pub fn compile_module(module: &Module, options: &CodegenOptions) -> Result<PtxModule> {
+    compile_ir_module(&llvm_parser::lower_module(module)?, options)
+}
+
+/// Lower every function of a module into one PTX module.
+pub fn compile_ir_module(module: &ir_model::Module, options: &CodegenOptions) -> Result<PtxModule> {
+    let layout = DataLayout::new(&module.data_layout, module.struct_types.clone());
+    let kernels = kernel_names(module, options);
+    let mut ptx = PtxModule::new(options.clone());
+    // `llvm.used` and the like are metadata for the linker.
+    let globals: Vec<GlobalVar> = module
+        .globals
+        .iter()
+        .filter(|g| !g.name.starts_with("llvm."))
+        .cloned()
+        .collect();
+    for global in &globals {
+        ptx.globals.push(declare_global(global, &globals, &layout)?);
+    }
+
+    for func in module.definitions() {
+        let function = Function {
+            kernel: kernels.contains(&func.name),
+            blocks: ir_model::mem2reg::promote_allocas(&func.blocks, &module.struct_types),
+            ..func.clone()
+        };
+
+        // Count unsupported instructions per function; outside permissive
+        // mode the first one fails the lowering instead.
+        let unhandled_count = function
+            .instructions()
             .filter(|i| matches!(i, Instruction::Unhandled { .. }))
             .count();
 
-        if unhandled_count > 0 {
+        if options.permissive && unhandled_count > 0 {
             eprintln!(
                 "Warning: {unhandled_count} unhandled instruction(s) in function `{}`",
-                kernel_name
+                function.name
             );
         }
 
-        let func_lines = lower_function(kernel_name, &blocks, "sm_75");
-        ptx_lines.extend(func_lines);
-        ptx_lines.push(String::new());
+        ptx.add_function(lower_function(&function, &globals, &layout, options)?);
+    }
+
+    // Functions only declared in the module are defined elsewhere; LLVM
+    // intrinsics are lowered inline and never declared.
+    let called: Vec<String> = ptx
+        .functions
+        .iter()
+        .flat_map(|f| f.callees.clone())
+        .collect();
+    for decl in module.declarations() {
+        if decl.name.starts_with("llvm.") || is_printf(&decl.name) || !called.contains(&decl.name) {
+            continue;
+        }
+        ptx.add_function(PtxFunction {
+            name: clean_operand(&decl.name),
+            kind: FunctionKind::Func,
+            linkage: Linkage::Extern,
+            ret: declare_return(&decl.name, FunctionKind::Func, &decl.signature.ret)?,
+            params: declare_params(&decl.name, &decl.signature, &layout)?,
+            body: vec![],
+            callees: vec![],
+        });
+    }
+    // `vprintf` has a fixed declaration, whatever the module says.
+    if called.iter().any(|c| c == VPRINTF) {
+        ptx.add_function(printf::vprintf_declaration());
     }
 
-    Ok(ptx_lines.join("\n"))
+    Ok(ptx)
 }
@@ -16,7 +16,9 @@
 // limitations under the License.
 
 use clap::Parser;
-use llvm_parser::parse_module::parse_module;
+use llvm_parser::{lower_module, nvvm_kernels, parse_llvm_ir_from_str, recover_atomic_orderings};
+use ptx_backend::compile_ir_module;
+use ptx_backend::module::{CodegenOptions, OnUnreachable};
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
@@ -27,11 +29,35 @@ struct Args {
     emit: bool,
     #[arg(long, default_value = "sm_75")]
     target: String,
+    /// PTX ISA version of the generated module.
+    #[arg(long, default_value = "7.0")]
+    ptx_version: String,
+    /// Emit this function as a kernel; may be repeated.
+    #[arg(long = "kernel")]
+    kernels: Vec<String>,
+    /// Emit unsupported instructions as comments instead of failing.
+    #[arg(long)]
+    permissive: bool,
+    /// Lower `unreachable` to `trap` (the default) or `exit`.
+    #[arg(long, default_value = "trap", value_parser = ["trap", "exit"])]
+    unreachable: String,
 }
 
 fn main() {
     let args = Args::parse();
-    let module = parse_module(&args.input).expect("invalid LLVM IR");
+    let ir = std::fs::read_to_string(&args.input).expect("cannot read input");
+    let module = parse_llvm_ir_from_str(&ir).expect("invalid LLVM IR");
+    let lowered = lower_module(&module).and_then(|mut module| {
+        recover_atomic_orderings(&mut module, &ir)?;
+        Ok(module)
+    });
+    let module = match lowered {
+        Ok(module) => module,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    };
 
     let mut output: Box<dyn Write> = if args.emit {
         Box::new(BufWriter::new(File::create("out.ptx").unwrap()))
@@ -39,29 +65,24 @@ fn main() {
         Box::new(std::io::stdout())
     };
 
-    for func in module.functions {
-        let instrs = func
-            .basic_blocks
-            .iter()
-            .map(|bb| {
-                let name = format!("{}", bb.name);
-                let mut lowered = bb
-                    .instrs
-                    .iter()
-                    .map(|instr| llvm_parser::convert::lower(&func.name, instr))
-                    .collect::<Vec<_>>();
-
-                lowered.push(llvm_parser::convert::lower_terminator(&func.name, &bb.term));
-
-                (name, lowered)
-            })
-            .collect::<Vec<_>>();
-
-        let lines = ptx_backend::lower_function(&func.name, &instrs, &args.target);
-        for line in lines {
-            writeln!(output, "{}", line).unwrap();
+    let mut kernels = args.kernels;
+    kernels.extend(nvvm_kernels(&ir));
+    let options = CodegenOptions {
+        target: args.target,
+        ptx_version: args.ptx_version,
+        kernels,
+        permissive: args.permissive,
+        unreachable: match args.unreachable.as_str() {
+            "exit" => OnUnreachable::Exit,
+            _ => OnUnreachable::Trap,
+        },
+    };
+    let ptx = match compile_ir_module(&module, &options) {
+        Ok(ptx) => ptx,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
         }
-        writeln!(output).unwrap(); // separación entre funciones
-    }
-
+    };
+    write!(output, "{}", ptx).unwrap();
 }
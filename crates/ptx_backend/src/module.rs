@@ -0,0 +1,263 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+// Module-level PTX compilation unit: one header, then globals, device
+// functions and kernels.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::ast::{Statement, VarDecl};
+use crate::printer::print_statements;
+
+/// Target settings shared by every function of a module.
+#[derive(Debug, Clone)]
+pub struct CodegenOptions {
+    /// `.target` architecture, e.g. `sm_75`.
+    pub target: String,
+    /// PTX ISA version written in the `.version` directive.
+    pub ptx_version: String,
+    /// Functions to emit as `.entry`, on top of those the IR marks as
+    /// kernels through `nvvm.annotations` or the `ptx_kernel` calling
+    /// convention.
+    pub kernels: Vec<String>,
+    /// Emit instructions the backend cannot lower as comments, instead of
+    /// failing. Meant for exploring unsupported input, the resulting PTX
+    /// does not assemble.
+    pub permissive: bool,
+    /// What LLVM `unreachable` becomes.
+    pub unreachable: OnUnreachable,
+}
+
+/// Lowering of LLVM `unreachable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnUnreachable {
+    /// `trap;`, aborting the kernel with an error the host can see.
+    #[default]
+    Trap,
+    /// `exit;`, quietly ending the thread.
+    Exit,
+}
+
+impl CodegenOptions {
+    /// Numeric SM version of the target, e.g. 75 for `sm_75`.
+    pub fn sm_version(&self) -> Option<u32> {
+        self.target
+            .strip_prefix("sm_")?
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .ok()
+    }
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions {
+            target: "sm_75".to_string(),
+            ptx_version: "7.0".to_string(),
+            kernels: vec![],
+            permissive: false,
+            unreachable: OnUnreachable::Trap,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    /// Kernel, launchable from the host.
+    Entry,
+    /// Device function, callable from other functions.
+    Func,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    /// Defined here and visible to other modules.
+    Visible,
+    /// Defined here, local to the module.
+    Internal,
+    /// Declared here, defined in another module.
+    Extern,
+}
+
+/// A lowered function.
+#[derive(Debug, Clone)]
+pub struct PtxFunction {
+    pub name: String,
+    pub kind: FunctionKind,
+    pub linkage: Linkage,
+    /// `.param` declaration of the return value, for device functions
+    /// returning one.
+    pub ret: Option<VarDecl>,
+    /// `.param` declarations of the arguments, in order.
+    pub params: Vec<VarDecl>,
+    /// Register declarations and instructions, without the braces.
+    pub body: Vec<Statement>,
+    /// Names of the functions called from the body.
+    pub callees: Vec<String>,
+}
+
+impl PtxFunction {
+    fn signature(&self) -> Vec<String> {
+        let linkage = match self.linkage {
+            Linkage::Visible => ".visible ",
+            Linkage::Internal => "",
+            Linkage::Extern => ".extern ",
+        };
+        let directive = match self.kind {
+            FunctionKind::Entry => ".entry",
+            FunctionKind::Func => ".func",
+        };
+        let ret = self
+            .ret
+            .as_ref()
+            .map(|r| format!("({r}) "))
+            .unwrap_or_default();
+        let mut lines = vec![format!("{linkage}{directive} {ret}{}(", self.name)];
+        for (i, param) in self.params.iter().enumerate() {
+            let sep = if i + 1 < self.params.len() { "," } else { "" };
+            lines.push(format!("    {param}{sep}"));
+        }
+        lines.push(")".into());
+        lines
+    }
+
+    /// Declaration of the function, for uses that precede its body.
+    pub fn prototype(&self) -> Vec<String> {
+        let mut lines = self.signature();
+        if let Some(last) = lines.last_mut() {
+            last.push(';');
+        }
+        lines
+    }
+
+    /// Full definition of the function.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("// Function: {}", self.name)];
+        lines.extend(self.signature());
+        lines.push("{".into());
+        lines.extend(print_statements(&self.body, 1));
+        lines.push("}".into());
+        lines
+    }
+}
+
+/// A whole PTX module, printed as a single compilation unit.
+#[derive(Debug, Clone, Default)]
+pub struct PtxModule {
+    pub options: CodegenOptions,
+    /// Module-scope variable declarations.
+    pub globals: Vec<VarDecl>,
+    pub functions: Vec<PtxFunction>,
+}
+
+impl PtxModule {
+    pub fn new(options: CodegenOptions) -> Self {
+        PtxModule {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_function(&mut self, function: PtxFunction) {
+        self.functions.push(function);
+    }
+
+    /// Device functions defined in the module, with every callee before
+    /// its callers and in source order otherwise. Recursive cycles keep
+    /// their source order and rely on the prototypes.
+    fn funcs_in_dependency_order(&self) -> Vec<&PtxFunction> {
+        fn visit<'a>(
+            function: &'a PtxFunction,
+            module: &'a PtxModule,
+            seen: &mut HashSet<&'a str>,
+            order: &mut Vec<&'a PtxFunction>,
+        ) {
+            if !seen.insert(&function.name) {
+                return;
+            }
+            for callee in &function.callees {
+                if let Some(f) = module
+                    .functions
+                    .iter()
+                    .find(|f| is_defined_func(f) && &f.name == callee)
+                {
+                    visit(f, module, seen, order);
+                }
+            }
+            order.push(function);
+        }
+
+        let mut seen = HashSet::new();
+        let mut order = vec![];
+        for function in &self.functions {
+            if is_defined_func(function) {
+                visit(function, self, &mut seen, &mut order);
+            }
+        }
+        order
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(".version {}", self.options.ptx_version),
+            format!(".target {}", self.options.target),
+            ".address_size 64".to_string(),
+            String::new(),
+        ];
+
+        if !self.globals.is_empty() {
+            lines.extend(self.globals.iter().map(|g| format!("{g};")));
+            lines.push(String::new());
+        }
+
+        let externs = self
+            .functions
+            .iter()
+            .filter(|f| f.linkage == Linkage::Extern);
+        let funcs = self.funcs_in_dependency_order();
+        if !funcs.is_empty() || externs.clone().next().is_some() {
+            for function in externs.chain(funcs.iter().copied()) {
+                lines.extend(function.prototype());
+            }
+            lines.push(String::new());
+        }
+
+        let entries = self
+            .functions
+            .iter()
+            .filter(|f| f.kind == FunctionKind::Entry);
+        for function in funcs.into_iter().chain(entries) {
+            lines.extend(function.lines());
+            lines.push(String::new());
+        }
+
+        lines
+    }
+}
+
+fn is_defined_func(function: &PtxFunction) -> bool {
+    function.kind == FunctionKind::Func && function.linkage != Linkage::Extern
+}
+
+impl fmt::Display for PtxModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines() {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Out-of-SSA translation: phi nodes are replaced by register copies on the
+// incoming edges.
+
+use std::collections::{HashMap, HashSet};
+
+use ir_model::cfg::Cfg;
+use ir_model::{BasicBlock, Instruction, Value};
+
+/// Replace every `Phi` with `Copy` instructions on its incoming edges.
+///
+/// Critical edges (from a block with several successors into a phi block
+/// with several predecessors) are split first, so a copy never runs on a
+/// path that does not reach the phi; this is what avoids the lost-copy
+/// problem. The copies of one edge form a parallel copy, which is
+/// sequentialized through a scratch register whenever it contains a cycle
+/// (the swap problem).
+pub fn eliminate_phis(blocks: &[BasicBlock]) -> Vec<BasicBlock> {
+    let mut blocks = blocks.to_vec();
+    if !blocks.iter().any(|block| block.instrs.iter().any(is_phi)) {
+        return blocks;
+    }
+
+    split_critical_edges(&mut blocks);
+
+    let cfg = Cfg::new(&blocks);
+
+    // Parallel copies to append to a predecessor, or to prepend to the phi
+    // block itself when the edge leaves a branching predecessor.
+    let mut at_end: HashMap<String, Vec<(Value, Value)>> = HashMap::new();
+    let mut at_start: HashMap<String, Vec<(Value, Value)>> = HashMap::new();
+
+    for BasicBlock { name, instrs } in &blocks {
+        for instr in instrs {
+            let Instruction::Phi { dst, incoming } = instr else {
+                continue;
+            };
+
+            let mut seen = HashSet::new();
+            for (pred, value) in incoming {
+                if !seen.insert(pred) || matches!(value, Value::Undef { .. }) {
+                    continue;
+                }
+                let copy = (dst.clone(), value.clone());
+                let branching = cfg.index(pred).is_some_and(|p| cfg.successors(p).len() > 1);
+                let single_pred = cfg
+                    .index(name)
+                    .is_some_and(|b| cfg.predecessors(b).len() == 1);
+                if branching && single_pred {
+                    at_start.entry(name.clone()).or_default().push(copy);
+                } else {
+                    at_end.entry(pred.clone()).or_default().push(copy);
+                }
+            }
+        }
+    }
+
+    blocks
+        .into_iter()
+        .map(|BasicBlock { name, instrs }| {
+            let mut out: Vec<Instruction> = vec![];
+            if let Some(copies) = at_start.get(&name) {
+                out.extend(sequentialize(copies));
+            }
+            out.extend(instrs.into_iter().filter(|i| !is_phi(i)));
+            if let Some(copies) = at_end.get(&name) {
+                let at = match out.last() {
+                    Some(last) if last.is_terminator() => out.len() - 1,
+                    _ => out.len(),
+                };
+                out.splice(at..at, sequentialize(copies));
+            }
+            BasicBlock::new(name, out)
+        })
+        .collect()
+}
+
+/// Turn a parallel copy into an equivalent sequence of copies.
+///
+/// A copy is safe to emit once no pending copy still reads its
+/// destination. When only cycles remain (`a <- b, b <- a`), one destination
+/// is saved in a scratch register and its readers are redirected there.
+fn sequentialize(copies: &[(Value, Value)]) -> Vec<Instruction> {
+    let mut pending: Vec<(Value, Value)> = copies
+        .iter()
+        .filter(|(dst, src)| dst != src)
+        .cloned()
+        .collect();
+    let mut out = vec![];
+
+    let reads = |pending: &[(Value, Value)], dst: &Value| {
+        pending
+            .iter()
+            .any(|(_, src)| src.local_name().is_some() && src.local_name() == dst.local_name())
+    };
+
+    while !pending.is_empty() {
+        if let Some(i) = pending.iter().position(|(dst, _)| !reads(&pending, dst)) {
+            let (dst, src) = pending.remove(i);
+            out.push(Instruction::Copy { dst, src });
+            continue;
+        }
+
+        let dst = pending[0].0.clone();
+        let scratch = Value::Local {
+            name: format!("{}$swap", dst.local_name().unwrap_or("phi")),
+            ty: dst.ty().clone(),
+        };
+        out.push(Instruction::Copy {
+            dst: scratch.clone(),
+            src: dst.clone(),
+        });
+        for (_, src) in pending.iter_mut() {
+            if src.local_name() == dst.local_name() {
+                *src = scratch.clone();
+            }
+        }
+    }
+
+    out
+}
+
+/// Split every critical edge that ends in a block with phis, by routing it
+/// through a new block holding a single unconditional branch.
+fn split_critical_edges(blocks: &mut Vec<BasicBlock>) {
+    let cfg = Cfg::new(blocks);
+    let phi_blocks: HashSet<String> = blocks
+        .iter()
+        .filter(|block| block.instrs.iter().any(is_phi))
+        .map(|block| block.name.clone())
+        .collect();
+    let mut names: HashSet<String> = blocks.iter().map(|block| block.name.clone()).collect();
+
+    let mut i = 0;
+    for b in 0..cfg.len() {
+        let pred = cfg.label(b).to_string();
+        let succs = cfg.successors(b);
+        if succs.len() < 2 {
+            i += 1;
+            continue;
+        }
+
+        let mut split_count = 0;
+        for &succ in succs {
+            let critical = phi_blocks.contains(cfg.label(succ)) && cfg.predecessors(succ).len() > 1;
+            let succ = cfg.label(succ).to_string();
+            if !critical {
+                continue;
+            }
+
+            let mut split = format!("{pred}_{succ}");
+            let mut n = 0;
+            while names.contains(&split) {
+                n += 1;
+                split = format!("{pred}_{succ}_{n}");
+            }
+            names.insert(split.clone());
+
+            if let Some(term) = blocks[i].instrs.last_mut() {
+                term.retarget(&succ, &split);
+            }
+            for BasicBlock { name, instrs } in blocks.iter_mut() {
+                if *name != succ {
+                    continue;
+                }
+                for instr in instrs.iter_mut() {
+                    if let Instruction::Phi { incoming, .. } = instr {
+                        for (label, _) in incoming.iter_mut() {
+                            if *label == pred {
+                                *label = split.clone();
+                            }
+                        }
+                    }
+                }
+            }
+
+            blocks.insert(
+                i + 1,
+                BasicBlock::new(
+                    split,
+                    vec![Instruction::Br {
+                        cond: None,
+                        target_true: succ,
+                        target_false: None,
+                    }],
+                ),
+            );
+            split_count += 1;
+        }
+        i += 1 + split_count;
+    }
+}
+
+fn is_phi(instr: &Instruction) -> bool {
+    matches!(instr, Instruction::Phi { .. })
+}
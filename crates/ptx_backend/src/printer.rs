@@ -0,0 +1,151 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+// Deterministic text form of the PTX AST.
+
+use std::fmt;
+
+use crate::ast::{Instruction, Operand, Predicate, Statement, VarDecl};
+
+const INDENT: &str = "    ";
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Reg(name) => write!(f, "%{name}"),
+            Operand::Imm(value) => write!(f, "{value}"),
+            Operand::F32(value) => write!(f, "0f{:08X}", value.to_bits()),
+            Operand::F64(value) => write!(f, "0d{:016X}", value.to_bits()),
+            Operand::Symbol(name) => write!(f, "{name}"),
+            Operand::Addr(base, 0) => write!(f, "[{base}]"),
+            Operand::Addr(base, offset) if *offset < 0 => write!(f, "[{base}{offset}]"),
+            Operand::Addr(base, offset) => write!(f, "[{base}+{offset}]"),
+            Operand::List(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bang = if self.negated { "!" } else { "" };
+        write!(f, "@{bang}%{}", self.reg)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(pred) = &self.pred {
+            write!(f, "{pred} ")?;
+        }
+        write!(f, "{}", self.opcode)?;
+        for modifier in &self.modifiers {
+            write!(f, ".{modifier}")?;
+        }
+        for (i, operand) in self.operands.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{sep}{operand}")?;
+        }
+        write!(f, ";")
+    }
+}
+
+/// Printed without the trailing `;`, which parameter lists do not use.
+impl fmt::Display for VarDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, ".{}", self.space.as_str().unwrap_or("generic"))?;
+        if let Some(align) = self.align {
+            write!(f, " .align {align}")?;
+        }
+        write!(f, " .{} {}", self.ty, self.name)?;
+        if let Some(len) = self.len {
+            write!(f, "[{len}]")?;
+        }
+        match (&self.init[..], self.len) {
+            ([], _) => {}
+            ([value], None) => write!(f, " = {value}")?,
+            (values, _) => {
+                let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                write!(f, " = {{{}}}", values.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Print body statements, one per line. Instructions are indented by
+/// `depth` levels; labels and register declarations sit one level out.
+pub fn print_statements(statements: &[Statement], depth: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for statement in statements {
+        print_statement(statement, depth, &mut lines);
+    }
+    lines
+}
+
+fn print_statement(statement: &Statement, depth: usize, lines: &mut Vec<String>) {
+    let indent = INDENT.repeat(depth);
+    let outer = INDENT.repeat(depth.saturating_sub(1));
+    match statement {
+        Statement::Label(name) => lines.push(format!("{outer}{name}:")),
+        Statement::Instr(instr) => lines.push(format!("{indent}{instr}")),
+        Statement::Regs(ty, regs) => {
+            let regs = regs
+                .iter()
+                .map(|r| format!("%{r}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(format!("{outer}.reg .{} {regs};", ty.as_str()));
+        }
+        Statement::Var(decl) => lines.push(format!("{indent}{decl};")),
+        Statement::Comment(text) => lines.push(format!("{indent}// {text}")),
+        Statement::BranchTargets(label, targets) => lines.push(format!(
+            "{outer}{label}: .branchtargets {};",
+            targets.join(", ")
+        )),
+        Statement::Prototype(prototype) => {
+            let params = prototype
+                .params
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let ret = match &prototype.ret {
+                Some(ret) => format!("({ret}) "),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "{outer}{}: .callprototype {ret}_ ({params});",
+                prototype.name
+            ));
+        }
+        Statement::Scope(inner) => {
+            lines.push(format!("{indent}{{"));
+            for statement in inner {
+                print_statement(statement, depth + 1, lines);
+            }
+            lines.push(format!("{indent}}}"));
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Device-side `printf`, through the CUDA runtime's `vprintf`.
+
+use ir_model::{Callee, Type, Value};
+
+use crate::addr_space::{AddressSpace, AddressSpaces};
+use crate::ast::{Instruction, Operand, Statement, VarDecl};
+use crate::call::lower_call;
+use crate::data_layout::DataLayout;
+use crate::error::CodegenError;
+use crate::module::{FunctionKind, Linkage, PtxFunction};
+use crate::ptx_type::PTXType;
+use crate::utils::clean_operand;
+
+/// Runtime function every `printf` ends up calling.
+pub const VPRINTF: &str = "vprintf";
+
+/// `.local` array holding the arguments of the function's `printf` calls.
+pub const BUFFER: &str = "__printf_buffer";
+
+/// Register holding the generic address of the argument buffer.
+const BUFFER_REG: &str = "printf$buf";
+
+/// Whether `callee` is `printf`, variadic, or `vprintf`, which takes an
+/// argument buffer built by the frontend.
+pub fn is_printf(callee: &str) -> bool {
+    callee == "printf" || callee == VPRINTF
+}
+
+/// `.extern .func (.param .b32 r) vprintf(.param .b64 fmt, .param .b64 args)`.
+pub fn vprintf_declaration() -> PtxFunction {
+    PtxFunction {
+        name: VPRINTF.to_string(),
+        kind: FunctionKind::Func,
+        linkage: Linkage::Extern,
+        ret: Some(VarDecl::param("b32", "r")),
+        params: vec![VarDecl::param("b64", "fmt"), VarDecl::param("b64", "args")],
+        body: vec![],
+        callees: vec![],
+    }
+}
+
+/// Type an argument is passed as, after the default argument promotions
+/// of a C variadic call: small integers become `int`, floats `double`.
+fn promoted(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Int(1..=32) => Some(Type::Int(32)),
+        Type::Int(64) | Type::Double | Type::Ptr { .. } => Some(ty.clone()),
+        Type::Half | Type::Float => Some(Type::Double),
+        _ => None,
+    }
+}
+
+/// Offset of each variadic argument in the buffer, each aligned to its
+/// own size, and the size of the buffer.
+fn buffer_layout(args: &[Value], layout: &DataLayout) -> Result<(Vec<u64>, u64), CodegenError> {
+    let mut offsets = vec![];
+    let mut size: u64 = 0;
+    for arg in args {
+        let ty = promoted(arg.ty()).ok_or_else(|| {
+            CodegenError::new(format!("unsupported printf argument type {:?}", arg.ty()))
+        })?;
+        let bytes = layout.size_of(&ty);
+        size = size.next_multiple_of(bytes);
+        offsets.push(size);
+        size += bytes;
+    }
+    Ok((offsets, size.next_multiple_of(8)))
+}
+
+/// Size of the argument buffer of a `printf` call, zero for `vprintf` and
+/// calls with no argument after the format.
+pub fn buffer_size(callee: &str, args: &[Value], layout: &DataLayout) -> u64 {
+    match args {
+        [_, varargs @ ..] if callee != VPRINTF => {
+            buffer_layout(varargs, layout).map_or(0, |(_, size)| size)
+        }
+        _ => 0,
+    }
+}
+
+/// `.local .align 8 .b8 __printf_buffer[size]`.
+pub fn buffer_decl(size: u64) -> VarDecl {
+    VarDecl {
+        space: AddressSpace::Local,
+        align: Some(8),
+        ty: "b8".to_string(),
+        name: BUFFER.to_string(),
+        len: Some(size),
+        init: vec![],
+    }
+}
+
+/// Scratch register converting a variadic argument register to its
+/// promoted type, with that type.
+fn promotion_scratch(arg: &Value) -> Option<(String, PTXType)> {
+    let name = clean_operand(arg.local_name()?);
+    match arg.ty() {
+        Type::Int(1..=16) => Some((format!("{name}$i32"), PTXType::S32)),
+        Type::Half | Type::Float => Some((format!("{name}$f64"), PTXType::F64)),
+        _ => None,
+    }
+}
+
+/// Scratch registers of a `printf` or `vprintf` call, with their types.
+pub fn printf_scratch(callee: &str, args: &[Value]) -> Vec<(String, PTXType)> {
+    let mut scratch = vec![];
+    if callee != VPRINTF && args.len() > 1 {
+        scratch.push((BUFFER_REG.to_string(), PTXType::Ptr));
+        scratch.extend(args[1..].iter().filter_map(promotion_scratch));
+    }
+    scratch
+}
+
+/// Lower `printf(fmt, ...)` into a `vprintf(fmt, args)` call. The format
+/// string is passed through; the variadic arguments, promoted, are
+/// stored into the function's `.local` buffer, whose generic address is
+/// passed as `args`. A `vprintf` call passes its buffer through.
+/// `uniform` is as for [`lower_call`].
+pub fn lower_printf(
+    callee: &str,
+    args: &[Value],
+    ret: Option<&Value>,
+    uniform: bool,
+    spaces: &AddressSpaces,
+    layout: &DataLayout,
+) -> Result<Vec<Statement>, CodegenError> {
+    let Some((format, varargs)) = args.split_first() else {
+        return Err(CodegenError::new(format!(
+            "{callee} without a format string"
+        )));
+    };
+    let pointer = |name: &str| Value::Local {
+        name: name.to_string(),
+        ty: Type::Ptr {
+            pointee: Box::new(Type::Int(8)),
+            addr_space: 0,
+        },
+    };
+
+    let mut statements = vec![];
+    let buffer = if callee == VPRINTF {
+        varargs.first().cloned().unwrap_or(Value::Null {
+            ty: format.ty().clone(),
+        })
+    } else if varargs.is_empty() {
+        Value::Null {
+            ty: format.ty().clone(),
+        }
+    } else {
+        let (offsets, _) = buffer_layout(varargs, layout)?;
+        for (arg, offset) in varargs.iter().zip(offsets) {
+            statements.extend(store_argument(arg, offset as i64)?);
+        }
+        statements.extend(buffer_address());
+        pointer(BUFFER_REG)
+    };
+
+    statements.extend(lower_call(
+        &Callee::Direct(VPRINTF.to_string()),
+        &[format.clone(), buffer],
+        &[],
+        ret,
+        uniform,
+        spaces,
+        layout,
+    )?);
+    Ok(statements)
+}
+
+/// `mov` the address of the `.local` buffer into its register and
+/// convert it to a generic address.
+fn buffer_address() -> Vec<Statement> {
+    let reg = Operand::Reg(BUFFER_REG.to_string());
+    vec![
+        Instruction::new(
+            "mov",
+            &["u64"],
+            vec![reg.clone(), Operand::Symbol(BUFFER.to_string())],
+        )
+        .into(),
+        Instruction::new("cvta", &["local", "u64"], vec![reg.clone(), reg]).into(),
+    ]
+}
+
+/// Promote a variadic argument and store it into the buffer at `offset`.
+fn store_argument(arg: &Value, offset: i64) -> Result<Vec<Statement>, CodegenError> {
+    let slot = Operand::Addr(Box::new(Operand::Symbol(BUFFER.to_string())), offset);
+    let mut statements = vec![];
+    let (ty, value) = match (arg, arg.ty()) {
+        (Value::ConstFloat { value, .. }, _) => ("f64", Operand::F64(*value)),
+        (Value::ConstInt { .. }, Type::Int(1)) => ("b32", Operand::from_value(arg)?),
+        // Promoted to `int` like a register argument, by sign extension.
+        (Value::ConstInt { .. }, Type::Int(2..=32)) => {
+            ("b32", Operand::Imm(arg.as_signed().unwrap_or_default()))
+        }
+        (_, Type::Int(1)) | (_, Type::Int(2..=16)) | (_, Type::Half) | (_, Type::Float) => {
+            let Some((scratch, scratch_ty)) = promotion_scratch(arg) else {
+                return Err(CodegenError::new(format!(
+                    "unsupported printf argument {arg:?}"
+                )));
+            };
+            let scratch = Operand::Reg(scratch);
+            let convert = match arg.ty() {
+                Type::Int(1) => Instruction::new(
+                    "selp",
+                    &["u32"],
+                    vec![
+                        scratch.clone(),
+                        Operand::Imm(1),
+                        Operand::Imm(0),
+                        Operand::from_value(arg)?,
+                    ],
+                ),
+                Type::Half => Instruction::new(
+                    "cvt",
+                    &["f64", "f16"],
+                    vec![scratch.clone(), Operand::from_value(arg)?],
+                ),
+                Type::Float => Instruction::new(
+                    "cvt",
+                    &["f64", "f32"],
+                    vec![scratch.clone(), Operand::from_value(arg)?],
+                ),
+                ty => Instruction::new(
+                    "cvt",
+                    &[
+                        "s32",
+                        PTXType::memory_from_ir(ty).unwrap_or(PTXType::S16).as_str(),
+                    ],
+                    vec![scratch.clone(), Operand::from_value(arg)?],
+                ),
+            };
+            statements.push(convert.into());
+            (
+                if scratch_ty == PTXType::F64 {
+                    "f64"
+                } else {
+                    "b32"
+                },
+                scratch,
+            )
+        }
+        (_, Type::Int(17..=32)) => ("b32", Operand::from_value(arg)?),
+        (_, Type::Double) => ("f64", Operand::from_value(arg)?),
+        _ => ("b64", Operand::from_value(arg)?),
+    };
+    statements.push(Instruction::new("st", &["local", ty], vec![slot, value]).into());
+    Ok(statements)
+}
@@ -17,35 +17,87 @@
 
 // PTX type system for register declaration and operand mapping.
 
+use ir_model::Type;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum PTXType {
+    /// Memory access width only: PTX has no 8-bit registers.
+    S8,
+    /// Memory access width only, for `i1` stored as a byte.
+    U8,
+    S16,
     S32,
     S64,
+    F16,
     F32,
     F64,
     Pred,
     Ptr,
 }
 
-// En ptx_type.rs
-impl PTXType {
-    pub fn from_str(s: &str) -> Self {
-        match s {
+impl std::str::FromStr for PTXType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "s8" => PTXType::S8,
+            "u8" => PTXType::U8,
+            "s16" => PTXType::S16,
             "s32" => PTXType::S32,
+            "s64" => PTXType::S64,
+            "f16" => PTXType::F16,
             "f32" => PTXType::F32,
+            "f64" => PTXType::F64,
             "pred" => PTXType::Pred,
-            "ptr" => PTXType::Ptr,
+            "ptr" | "u64" => PTXType::Ptr,
             _ => PTXType::S32, // default fallback
-        }
+        })
     }
 }
 
 impl PTXType {
+    /// Register type holding a value of the given LLVM type, or `None` when
+    /// the type has no scalar register representation.
+    ///
+    /// PTX has no 8-bit registers, so `i8` lives in a 16-bit register like
+    /// NVPTX does; `i1` becomes a predicate. These are register types only:
+    /// the width of a load or store comes from [`memory_from_ir`].
+    ///
+    /// [`memory_from_ir`]: PTXType::memory_from_ir
+    pub fn from_ir(ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Int(1) => Some(PTXType::Pred),
+            Type::Int(2..=16) => Some(PTXType::S16),
+            Type::Int(17..=32) => Some(PTXType::S32),
+            Type::Int(33..=64) => Some(PTXType::S64),
+            Type::Half => Some(PTXType::F16),
+            Type::Float => Some(PTXType::F32),
+            Type::Double => Some(PTXType::F64),
+            Type::Ptr { .. } => Some(PTXType::Ptr),
+            _ => None,
+        }
+    }
+
+    /// Type of a load or store of a value of the given LLVM type, which
+    /// accesses exactly its bytes: `s8` for `i8`, and `u8` for `i1`, kept
+    /// in memory as a byte. Other types access their register width.
+    pub fn memory_from_ir(ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Int(1) => Some(PTXType::U8),
+            Type::Int(2..=8) => Some(PTXType::S8),
+            ty => PTXType::from_ir(ty),
+        }
+    }
+
     /// Returns the string representation used in PTX code
     pub fn as_str(&self) -> &'static str {
         match self {
+            PTXType::S8 => "s8",
+            PTXType::U8 => "u8",
+            PTXType::S16 => "s16",
             PTXType::S32 => "s32",
             PTXType::S64 => "s64",
+            PTXType::F16 => "f16",
             PTXType::F32 => "f32",
             PTXType::F64 => "f64",
             PTXType::Pred => "pred",
@@ -53,6 +105,52 @@ impl PTXType {
         }
     }
 
+    /// Untyped bit-size form of the register type (`b32`, `b64`...), as
+    /// used by bitwise instructions and `mov` between register types.
+    /// Predicates stay `pred`.
+    pub fn as_bits(&self) -> &'static str {
+        match self {
+            PTXType::S8 | PTXType::U8 => "b8",
+            PTXType::S16 | PTXType::F16 => "b16",
+            PTXType::S32 | PTXType::F32 => "b32",
+            PTXType::S64 | PTXType::F64 | PTXType::Ptr => "b64",
+            PTXType::Pred => "pred",
+        }
+    }
+
+    /// Signed (`s*`) or unsigned (`u*`) integer form of an integer or
+    /// pointer register type, as arithmetic and comparisons spell it.
+    pub fn as_int(&self, signed: bool) -> Option<&'static str> {
+        let ty = match (self, signed) {
+            (PTXType::S8 | PTXType::U8, true) => "s8",
+            (PTXType::S8 | PTXType::U8, false) => "u8",
+            (PTXType::S16, true) => "s16",
+            (PTXType::S16, false) => "u16",
+            (PTXType::S32, true) => "s32",
+            (PTXType::S32, false) => "u32",
+            (PTXType::S64 | PTXType::Ptr, true) => "s64",
+            (PTXType::S64 | PTXType::Ptr, false) => "u64",
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// Type of the `.param` variable used to pass a value of the given LLVM
+    /// type, or `None` when it cannot be passed as a scalar parameter.
+    pub fn param_type(ty: &Type) -> Option<&'static str> {
+        match ty {
+            Type::Int(1..=8) => Some("u8"),
+            Type::Int(9..=16) => Some("u16"),
+            Type::Int(17..=32) => Some("u32"),
+            Type::Int(33..=64) => Some("u64"),
+            Type::Half => Some("b16"),
+            Type::Float => Some("f32"),
+            Type::Double => Some("f64"),
+            Type::Ptr { .. } => Some("u64"),
+            _ => None,
+        }
+    }
+
     /// Given two types used in same register, return the dominant type
     pub fn dominant_with(self, other: PTXType) -> PTXType {
         use PTXType::*;
@@ -0,0 +1,183 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Multi-way branches: compare chains and `brx.idx` jump tables.
+
+use ir_model::{Type, Value};
+
+use crate::ast::{Instruction, Operand, Statement};
+use crate::error::CodegenError;
+use crate::ptx_type::PTXType;
+use crate::utils::{clean_operand, label_name};
+
+/// Fewest cases for which a jump table beats a compare chain.
+const MIN_TABLE_CASES: usize = 4;
+
+/// Dense switch, lowered to `brx.idx`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpTable {
+    /// Case value of the first entry.
+    pub low: i64,
+    /// One target per value from `low` on; holes go to the default.
+    pub targets: Vec<String>,
+}
+
+/// Jump table for a switch whose cases fill at least half of their range.
+/// `brx.idx` takes a 32-bit index, so wider conditions always use a chain.
+pub fn jump_table(cond: &Value, default: &str, cases: &[(Value, String)]) -> Option<JumpTable> {
+    if cases.len() < MIN_TABLE_CASES || cond.local_name().is_none() || *cond.ty() != Type::Int(32) {
+        return None;
+    }
+    let values = cases
+        .iter()
+        .map(|(value, _)| value.as_signed())
+        .collect::<Option<Vec<i64>>>()?;
+    let low = *values.iter().min()?;
+    let high = *values.iter().max()?;
+    let len = usize::try_from(high.checked_sub(low)? + 1).ok()?;
+    if len > 2 * cases.len() {
+        return None;
+    }
+
+    let mut targets = vec![default.to_string(); len];
+    for (value, (_, target)) in values.iter().zip(cases) {
+        targets[(value - low) as usize] = target.clone();
+    }
+    Some(JumpTable { low, targets })
+}
+
+/// Mask of the bits of a switch condition narrower than its register,
+/// whose high bits are unspecified; `None` when it fills the register.
+pub fn condition_mask(cond: &Value) -> Option<i64> {
+    match cond.ty().int_bits()? {
+        1 | 16 | 32 | 64 => None,
+        bits => Some((1i64 << bits) - 1),
+    }
+}
+
+/// Predicate and index scratch registers of a switch on `cond`.
+pub fn switch_scratch(cond: &Value) -> Option<(String, String)> {
+    let name = clean_operand(cond.local_name()?);
+    Some((format!("{name}$sw"), format!("{name}$swidx")))
+}
+
+/// Lower a switch to a jump table when dense enough, else to one
+/// compare-and-branch per case followed by a branch to the default.
+/// `block` is the block the switch ends, which names its jump table.
+pub fn lower_switch(
+    cond: &Value,
+    default: &str,
+    cases: &[(Value, String)],
+    block: &str,
+) -> Result<Vec<Statement>, CodegenError> {
+    let bra =
+        |target: &str| Instruction::new("bra", &[], vec![Operand::Symbol(label_name(target))]);
+
+    // A constant condition (left by an unoptimised frontend) picks its
+    // target right away.
+    let Some((pred, index)) = switch_scratch(cond) else {
+        let target = cases
+            .iter()
+            .find(|(value, _)| value.as_signed() == cond.as_signed())
+            .map_or(default, |(_, target)| target.as_str());
+        return Ok(vec![bra(target).into()]);
+    };
+    let pred = Operand::Reg(pred);
+    let ty = PTXType::from_ir(cond.ty())
+        .filter(|_| cond.ty().is_integer())
+        .ok_or_else(|| CodegenError::new(format!("unsupported switch type: {:?}", cond.ty())))?
+        .as_bits();
+
+    let mut statements = vec![];
+    if let Some(table) = jump_table(cond, default, cases) {
+        let index = if table.low == 0 {
+            Operand::from_value(cond)?
+        } else {
+            let index = Operand::Reg(index);
+            statements.push(
+                Instruction::new(
+                    "sub",
+                    &["s32"],
+                    vec![
+                        index.clone(),
+                        Operand::from_value(cond)?,
+                        Operand::Imm(table.low),
+                    ],
+                )
+                .into(),
+            );
+            index
+        };
+        let table_name = format!("$L__JT_{}", clean_operand(block));
+        statements.push(
+            Instruction::new(
+                "setp",
+                &["ge", "u32"],
+                vec![
+                    pred.clone(),
+                    index.clone(),
+                    Operand::Imm(table.targets.len() as i64),
+                ],
+            )
+            .into(),
+        );
+        statements.push(bra(default).guarded(&pred).into());
+        statements.push(Statement::BranchTargets(
+            table_name.clone(),
+            table.targets.iter().map(|t| label_name(t)).collect(),
+        ));
+        statements.push(
+            Instruction::new("brx", &["idx"], vec![index, Operand::Symbol(table_name)]).into(),
+        );
+        return Ok(statements);
+    }
+
+    // Narrow conditions are compared on a masked copy in the index
+    // register, against case values masked the same way.
+    let mask = condition_mask(cond);
+    let cond = match mask {
+        Some(mask) => {
+            let masked = Operand::Reg(index);
+            statements.push(
+                Instruction::new(
+                    "and",
+                    &[ty],
+                    vec![
+                        masked.clone(),
+                        Operand::from_value(cond)?,
+                        Operand::Imm(mask),
+                    ],
+                )
+                .into(),
+            );
+            masked
+        }
+        None => Operand::from_value(cond)?,
+    };
+    for (value, target) in cases {
+        let value = match (mask, value.as_signed()) {
+            (Some(mask), Some(v)) => Operand::Imm(v & mask),
+            _ => Operand::from_value(value)?,
+        };
+        statements.push(
+            Instruction::new("setp", &["eq", ty], vec![pred.clone(), cond.clone(), value]).into(),
+        );
+        statements.push(bra(target).guarded(&pred).into());
+    }
+    statements.push(bra(default).into());
+    Ok(statements)
+}
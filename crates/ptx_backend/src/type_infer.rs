@@ -0,0 +1,71 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Register type inference from the LLVM types carried by the IR model.
+
+use anyhow::{Result, bail};
+use ir_model::{BasicBlock, Value};
+
+use crate::ptx_type::PTXType;
+use crate::type_map::TypeMap;
+use crate::utils::clean_operand;
+
+/// Build the register type map of a function.
+///
+/// Every local value gets the PTX register type of its LLVM type: the
+/// result type for instruction destinations and the operand type for
+/// parameters and other uses. Values without a scalar register
+/// representation (aggregates, labels...) are left out.
+///
+/// Two LLVM values mapping to the same PTX register with different types
+/// is a hard error, since declaring either type would miscompile the other.
+pub fn infer_types(blocks: &[BasicBlock]) -> Result<TypeMap> {
+    let mut type_map = TypeMap::new();
+
+    for block in blocks {
+        for instr in &block.instrs {
+            for value in instr.values() {
+                record(&mut type_map, value, &block.name)?;
+            }
+        }
+    }
+
+    Ok(type_map)
+}
+
+/// Record the register type of a single value, checking it against any
+/// type already inferred for the same register.
+pub fn record(type_map: &mut TypeMap, value: &Value, block: &str) -> Result<()> {
+    let Value::Local { name, ty } = value else {
+        return Ok(());
+    };
+    let Some(ptx_ty) = PTXType::from_ir(ty) else {
+        return Ok(());
+    };
+
+    let reg = clean_operand(name);
+    match type_map.get(&reg) {
+        Some(prev) if *prev != ptx_ty => bail!(
+            "register %{reg} in block `{block}` has conflicting types .{} and .{}",
+            prev.as_str(),
+            ptx_ty.as_str()
+        ),
+        Some(_) => {}
+        None => type_map.insert(&reg, ptx_ty),
+    }
+    Ok(())
+}
@@ -62,21 +62,34 @@ impl TypeMap {
     }
 }
 
-
-pub fn declare_registers_from_typemap(map: &TypeMap) -> Vec<String> {
+/// Registers grouped by type, both in a stable order.
+pub fn group_registers(map: &TypeMap) -> Vec<(PTXType, Vec<String>)> {
     use std::collections::BTreeMap;
-    let mut reg_by_type: BTreeMap<PTXType, Vec<&str>> = BTreeMap::new();
+    let mut reg_by_type: BTreeMap<PTXType, Vec<String>> = BTreeMap::new();
 
     for (reg, ty) in map.all() {
-        reg_by_type.entry(*ty).or_default().push(reg);
+        reg_by_type.entry(*ty).or_default().push(reg.clone());
     }
 
-    let mut lines = vec![];
-    for (ty, mut regs) in reg_by_type {
-        regs.sort(); // 🔥 Aquí imponemos orden alfabético en los nombres de registros
-        let regs_str = regs.iter().map(|r| format!("%{}", r)).collect::<Vec<_>>().join(", ");
-        lines.push(format!(".reg {} {};", ty.as_str(), regs_str));
-    }
+    reg_by_type
+        .into_iter()
+        .map(|(ty, mut regs)| {
+            regs.sort(); // 🔥 Aquí imponemos orden alfabético en los nombres de registros
+            (ty, regs)
+        })
+        .collect()
+}
 
-    lines
+pub fn declare_registers_from_typemap(map: &TypeMap) -> Vec<String> {
+    group_registers(map)
+        .into_iter()
+        .map(|(ty, regs)| {
+            let regs_str = regs
+                .iter()
+                .map(|r| format!("%{}", r))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(".reg .{} {};", ty.as_str(), regs_str)
+        })
+        .collect()
 }
@@ -15,75 +15,37 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use ir_model::Instruction;
-
-/// Clean LLVM operand string for PTX emission.
+/// Clean an LLVM name for PTX emission.
 ///
-/// This strips decorations (`%`, `@`), removes type annotations and
-/// pointer indicators, and trims spaces.
+/// This strips decorations (`%`, `@`) and rewrites characters that are not
+/// valid in PTX identifiers. Dots, which LLVM uses freely (`%x.addr`), become
+/// `_$_` the same way NVPTX mangles them, so distinct names stay distinct.
 ///
 /// Examples:
-/// - `"i32* %i"` → `"i"`
-/// - `"float* %x"` → `"x"`
-/// - `"  f32* %y "` → `"y"`
+/// - `"%i"` → `"i"`
+/// - `"@saxpy"` → `"saxpy"`
+/// - `"%x.addr"` → `"x_$_addr"`
 pub fn clean_operand(op: &str) -> String {
-    let mut s = op
-        .trim()
-        .split_whitespace()
-        .last()
-        .unwrap_or(op)
-        .trim_start_matches('%')
-        .trim_start_matches('@')
-        .to_string();
-
-    for pat in ["*", "i32", "float", "f32", "ptr", " "] {
-        s = s.replace(pat, "");
+    let s = op.trim().trim_start_matches('%').trim_start_matches('@');
+
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '$' => out.push(c),
+            '.' => out.push_str("_$_"),
+            _ => out.push('_'),
+        }
     }
-    s
+    out
 }
 
-pub fn get_register_type(instr: &Instruction, name: &str) -> Option<&'static str> {
-    use Instruction::*;
-    let matches = |s: &str| clean_operand(s) == clean_operand(name);
-
-    match instr {
-        FMul { dst, lhs, rhs, .. } | FAdd { dst, lhs, rhs, .. }
-            if matches(dst) || matches(lhs) || matches(rhs) =>
-        {
-            Some("f32")
-        }
-
-        Load { dst, .. } if matches(dst) => {
-            if clean_operand(dst).starts_with('x')
-                || clean_operand(dst).starts_with('y')
-                || clean_operand(dst).starts_with('a')
-                || clean_operand(dst).contains("val")
-            {
-                Some("f32")
-            } else {
-                Some("s32")
-            }
-        }
-
-        Store { value, .. } if matches(value) => {
-            let val = clean_operand(value);
-            if val.starts_with('x')
-                || val.starts_with('y')
-                || val.starts_with('a')
-                || val.contains("val")
-            {
-                Some("f32")
-            } else {
-                Some("s32")
-            }
-        }
-
-        Add { dst, lhs, rhs, .. } if matches(dst) || matches(lhs) || matches(rhs) => Some("s32"),
-
-        ICmp { lhs, rhs, .. } if matches(lhs) || matches(rhs) => Some("s32"),
-
-        ICmp { dst, .. } if matches(dst) => Some("pred"),
-
-        _ => None,
+/// Render a block name as a PTX label. Numbered LLVM blocks (`%3`) are not
+/// valid PTX identifiers, so they get a `$L__BB` prefix.
+pub fn label_name(block: &str) -> String {
+    let clean = clean_operand(block);
+    if clean.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("$L__BB{}", clean)
+    } else {
+        clean
     }
 }
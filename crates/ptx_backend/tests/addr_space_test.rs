@@ -0,0 +1,85 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use ptx_backend::compile_llvm_to_ptx;
+
+#[test]
+fn explicit_address_spaces_select_the_state_space() {
+    let ptx = compile_llvm_to_ptx(
+        r#"
+define void @spaces(float addrspace(1)* %g, float addrspace(3)* %s, float addrspace(4)* %c, float* %p) {
+entry:
+  %a = load float, float addrspace(4)* %c
+  %b = load float, float addrspace(3)* %s
+  %d = fadd float %a, %b
+  store float %d, float addrspace(1)* %g
+  store float %d, float* %p
+  ret void
+}
+"#,
+    )
+    .expect("Compilation failed");
+
+    assert!(ptx.contains("ld.const.f32 %a, [%c];"), "{ptx}");
+    assert!(ptx.contains("ld.shared.f32 %b, [%s];"), "{ptx}");
+    assert!(ptx.contains("st.global.f32 [%g], %d;"), "{ptx}");
+    // Generic kernel pointers are proven global.
+    assert!(ptx.contains("st.global.f32 [%p], %d;"), "{ptx}");
+}
+
+#[test]
+fn addrspacecast_origin_is_propagated() {
+    let ptx = compile_llvm_to_ptx(
+        r#"
+define void @cast(float addrspace(3)* %s, float addrspace(5)* %l) {
+entry:
+  %gs = addrspacecast float addrspace(3)* %s to float*
+  %gl = addrspacecast float addrspace(5)* %l to float*
+  %v = load float, float* %gs
+  store float %v, float* %gl
+  ret void
+}
+"#,
+    )
+    .expect("Compilation failed");
+
+    assert!(ptx.contains("mov.u64 %gs, %s;"), "{ptx}");
+    assert!(ptx.contains("ld.shared.f32 %v, [%gs];"), "{ptx}");
+    assert!(ptx.contains("st.local.f32 [%gl], %v;"), "{ptx}");
+}
+
+#[test]
+fn escaping_pointers_stay_generic() {
+    let ptx = compile_llvm_to_ptx(
+        r#"
+define void @escape(float addrspace(3)* %s, float** %out, float* %p) {
+entry:
+  %gs = addrspacecast float addrspace(3)* %s to float*
+  store float* %gs, float** %out
+  %v = load float, float* %gs
+  %q = load float*, float** %out
+  store float %v, float* %q
+  ret void
+}
+"#,
+    )
+    .expect("Compilation failed");
+
+    assert!(ptx.contains("cvta.shared.u64 %gs, %s;"), "{ptx}");
+    assert!(ptx.contains("ld.f32 %v, [%gs];"), "{ptx}");
+    assert!(ptx.contains("st.global.u64 [%out], %gs;"), "{ptx}");
+    assert!(ptx.contains("st.f32 [%q], %v;"), "{ptx}");
+}
@@ -0,0 +1,101 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ptx_backend::compile_module;
+use ptx_backend::error::CodegenError;
+use ptx_backend::module::CodegenOptions;
+
+fn compile(ir: &str, target: &str) -> anyhow::Result<String> {
+    let module = llvm_parser::parse_llvm_ir_from_str(ir).unwrap();
+    let options = CodegenOptions {
+        target: target.to_string(),
+        ..Default::default()
+    };
+    Ok(compile_module(&module, &options)?.to_string())
+}
+
+const ACQUIRE_ADD: &str = r#"
+define void @k(i32 addrspace(3)* %p, i32* %out) {
+entry:
+  %old = atomicrmw add i32 addrspace(3)* %p, i32 1 acquire
+  store i32 %old, i32* %out, align 4
+  ret void
+}
+"#;
+
+#[test]
+fn ordering_is_qualified_from_sm_70() {
+    let ptx = compile(ACQUIRE_ADD, "sm_70").unwrap();
+
+    assert!(
+        ptx.contains("atom.acquire.sys.shared.add.u32 %old, [%p], 1;"),
+        "{ptx}"
+    );
+}
+
+#[test]
+fn ordering_uses_membar_before_sm_70() {
+    let ptx = compile(ACQUIRE_ADD, "sm_60").unwrap();
+
+    assert!(
+        ptx.contains("atom.shared.add.u32 %old, [%p], 1;\n    membar.sys;"),
+        "{ptx}"
+    );
+}
+
+#[test]
+fn acquire_results_are_not_reduced() {
+    let ptx = compile(
+        r#"
+define void @k(i32* %p) {
+entry:
+  %a = atomicrmw or i32* %p, i32 4 acquire
+  %b = atomicrmw or i32* %p, i32 4 monotonic
+  ret void
+}
+"#,
+        "sm_75",
+    )
+    .unwrap();
+
+    assert!(
+        ptx.contains("atom.acquire.sys.global.or.b32 %a, [%p], 4;"),
+        "{ptx}"
+    );
+    assert!(
+        ptx.contains("red.relaxed.sys.global.or.b32 [%p], 4;"),
+        "{ptx}"
+    );
+}
+
+#[test]
+fn nand_is_an_error() {
+    let err = compile(
+        r#"
+define void @k(i32* %p) {
+entry:
+  %a = atomicrmw nand i32* %p, i32 4 monotonic
+  ret void
+}
+"#,
+        "sm_75",
+    )
+    .unwrap_err();
+
+    let err = err.downcast_ref::<CodegenError>().unwrap();
+    assert!(err.message.contains("Nand"), "{err}");
+}
@@ -23,7 +23,11 @@ const CALL_LL: &str = include_str!("inputs/call.ll");
 fn test_call_translation() {
     let ptx = compile_llvm_to_ptx(CALL_LL).expect("Compilation failed");
 
-    assert!(ptx.contains("call foo, ();"), "Missing call to foo");
+    assert!(ptx.contains("call.uni foo, ();"), "Missing call to foo");
     assert!(ptx.contains(".entry main"), "Missing main entry");
-    assert!(ptx.contains(".entry foo"), "Missing foo entry");
+    assert!(ptx.contains(".func foo"), "Missing foo device function");
+    assert!(
+        !ptx.contains(".entry foo"),
+        "Called helper emitted as a kernel"
+    );
 }
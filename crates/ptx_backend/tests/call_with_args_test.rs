@@ -27,21 +27,21 @@ fn test_call_with_args_translation() {
     println!("===== PTX Output =====\n{ptx}\n=======================");
 
     // Validaciones por string directo
-    assert!(ptx.contains(".param .s32 arg0;"));
-    assert!(ptx.contains(".param .f32 arg1;"));
-    assert!(ptx.contains("call foo, (arg0, arg1);"));
+    assert!(ptx.contains(".param .b32 param0;"));
+    assert!(ptx.contains(".param .b32 param1;"));
+    assert!(ptx.contains("call.uni foo, (param0, param1);"));
 
     // Validaciones por patrón
-    let re_arg0 = Regex::new(r"st\.param\.\w+\s+\[arg0],\s+%[a-zA-Z0-9_]+;").unwrap();
-    let re_arg1 = Regex::new(r"st\.param\.\w+\s+\[arg1],\s+%[a-zA-Z0-9_]+;").unwrap();
+    let re_arg0 = Regex::new(r"st\.param\.\w+\s+\[param0],\s+%[a-zA-Z0-9_]+;").unwrap();
+    let re_arg1 = Regex::new(r"st\.param\.\w+\s+\[param1],\s+%[a-zA-Z0-9_]+;").unwrap();
 
     assert!(
         re_arg0.is_match(&ptx),
-        "Expected st.param.b32 to arg0 from some register"
+        "Expected st.param.b32 to param0 from some register"
     );
 
     assert!(
         re_arg1.is_match(&ptx),
-        "Expected st.param.b32 to arg1 from some register"
+        "Expected st.param.f32 to param1 from some register"
     );
 }
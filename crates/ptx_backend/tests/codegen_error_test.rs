@@ -0,0 +1,163 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ptx_backend::compile_llvm_to_ptx;
+use ptx_backend::compile_module;
+use ptx_backend::error::CodegenError;
+use ptx_backend::module::CodegenOptions;
+
+const VA_ARG_LL: &str = r#"
+define void @k(i8** %ap, i32* %out) {
+entry:
+  br label %body
+
+body:
+  %v = va_arg i8** %ap, i32
+  store i32 %v, i32* %out
+  ret void
+}
+"#;
+
+#[test]
+fn unhandled_instruction_is_an_error_with_context() {
+    let err = compile_llvm_to_ptx(VA_ARG_LL).unwrap_err();
+    let err = err
+        .downcast_ref::<CodegenError>()
+        .unwrap_or_else(|| panic!("not a CodegenError: {err}"));
+
+    assert_eq!(err.function.as_deref(), Some("k"));
+    assert_eq!(err.block.as_deref(), Some("body"));
+    assert_eq!(err.message, "unhandled");
+    assert!(
+        err.instruction.as_deref().unwrap().contains("VAArg"),
+        "{err}"
+    );
+    assert!(
+        err.to_string()
+            .starts_with("in function `k`, block `body`: ")
+    );
+}
+
+#[test]
+fn permissive_mode_keeps_comments() {
+    let module = llvm_parser::parse_llvm_ir_from_str(VA_ARG_LL).unwrap();
+    let options = CodegenOptions {
+        permissive: true,
+        ..Default::default()
+    };
+    let ptx = compile_module(&module, &options).unwrap().to_string();
+
+    assert!(ptx.contains("    // unhandled:"), "{ptx}");
+    assert!(ptx.contains("st.global.s32 [%out], %v;"), "{ptx}");
+}
+
+#[test]
+fn unordered_fcmp_predicates_are_kept() {
+    let ptx = compile_llvm_to_ptx(
+        r#"
+define void @k(float %a, float %b) {
+entry:
+  %c = fcmp ult float %a, %b
+  %n = fcmp uno float %a, %b
+  ret void
+}
+"#,
+    )
+    .unwrap();
+
+    assert!(ptx.contains("setp.ltu.f32 %c, %a, %b;"), "{ptx}");
+    assert!(ptx.contains("setp.nan.f32 %n, %a, %b;"), "{ptx}");
+}
+
+#[test]
+fn constant_fcmp_predicates_are_errors() {
+    let err = compile_llvm_to_ptx(
+        r#"
+define void @k(float %a, float %b) {
+entry:
+  %c = fcmp true float %a, %b
+  ret void
+}
+"#,
+    )
+    .unwrap_err();
+
+    let err = err.downcast_ref::<CodegenError>().unwrap();
+    assert!(err.message.contains("fcmp"), "{err}");
+}
+
+const CONST_EXPR_LL: &str = r#"
+@g = addrspace(1) global i32 0
+
+define void @k(i64* %out) {
+entry:
+  store i64 ptrtoint (i32 addrspace(1)* @g to i64), i64* %out
+  ret void
+}
+"#;
+
+#[test]
+fn unhandled_value_is_an_error_with_context() {
+    let err = compile_llvm_to_ptx(CONST_EXPR_LL).unwrap_err();
+    let err = err
+        .downcast_ref::<CodegenError>()
+        .unwrap_or_else(|| panic!("not a CodegenError: {err}"));
+
+    assert_eq!(err.function.as_deref(), Some("k"));
+    assert_eq!(err.block.as_deref(), Some("entry"));
+    assert!(err.message.starts_with("unhandled value: "), "{err}");
+    assert!(err.message.contains("ptrtoint"), "{err}");
+}
+
+#[test]
+fn permissive_mode_comments_unhandled_values() {
+    let module = llvm_parser::parse_llvm_ir_from_str(CONST_EXPR_LL).unwrap();
+    let options = CodegenOptions {
+        permissive: true,
+        ..Default::default()
+    };
+    let ptx = compile_module(&module, &options).unwrap().to_string();
+
+    assert!(ptx.contains("    // unhandled value: "), "{ptx}");
+    assert!(!ptx.contains("st.s64"), "{ptx}");
+    assert!(ptx.contains("    ret;"), "{ptx}");
+}
+
+#[test]
+fn unnamed_byval_argument_is_an_error() {
+    let err = compile_llvm_to_ptx(
+        r#"
+%struct.pair = type { float, i32 }
+
+define void @take(%struct.pair* byval(%struct.pair) align 4 %p) {
+entry:
+  ret void
+}
+
+define void @k() {
+entry:
+  call void @take(%struct.pair* byval(%struct.pair) align 4 null)
+  ret void
+}
+"#,
+    )
+    .unwrap_err();
+
+    let err = err.downcast_ref::<CodegenError>().unwrap();
+    assert_eq!(err.function.as_deref(), Some("k"));
+    assert!(err.message.contains("byval"), "{err}");
+}
@@ -16,21 +16,17 @@
 // limitations under the License.
 
 use ir_model::Instruction;
-use ptx_backend::ptx_type::PTXType;
+use ptx_backend::type_infer::record;
 use ptx_backend::type_map::TypeMap;
-use ptx_backend::utils::{clean_operand, get_register_type};
 
 pub fn build_typemap(instrs: &[&Instruction]) -> TypeMap {
     let mut type_map = TypeMap::new();
 
     for instr in instrs {
-        for operand in instr.used_operands() {
-            if let Some(ty_str) = get_register_type(instr, operand) {
-                let ptx_ty = PTXType::from_str(ty_str);
-                type_map.insert(&clean_operand(operand), ptx_ty);
-            }
+        for value in instr.values() {
+            record(&mut type_map, value, "entry").expect("conflicting register types");
         }
     }
 
     type_map
-}
\ No newline at end of file
+}
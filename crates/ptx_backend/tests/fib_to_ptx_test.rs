@@ -15,8 +15,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use ir_model::BasicBlock;
 use llvm_parser::parse_module::parse_module;
-use ptx_backend::{to_ptx};
+use ptx_backend::addr_space::infer_address_spaces;
+use ptx_backend::data_layout::DataLayout;
+use ptx_backend::to_ptx;
 use ptx_backend::type_map::declare_registers_from_typemap;
 
 mod common;
@@ -38,6 +41,8 @@ fn test_fib_to_ptx() {
     actual.push_str(".target sm_75\n");
     actual.push_str(".address_size 64\n\n");
 
+    let layout = DataLayout::from_module(&module);
+
     for func in module.functions {
         actual.push_str(&format!("// Function: {}\n", func.name));
 
@@ -49,12 +54,14 @@ fn test_fib_to_ptx() {
                 block
                     .instrs
                     .iter()
-                    .map(|i| llvm_parser::convert::lower(&func.name, i)),
+                    .map(|i| llvm_parser::convert::lower(i, &module.types)),
             );
         }
 
         let instr_refs: Vec<&_> = instrs.iter().collect();
         let type_map = build_typemap(&instr_refs);
+        let params = llvm_parser::lower_params(&func);
+        let spaces = infer_address_spaces(&[BasicBlock::new("body", instrs.clone())], &params);
 
         let decls = declare_registers_from_typemap(&type_map);
         actual.push_str(&decls.join("\n"));
@@ -62,7 +69,10 @@ fn test_fib_to_ptx() {
 
         actual.push_str(&format!(".entry {} {{\n", func.name));
         for instr in &instrs {
-            actual.push_str(&format!("    {}\n", to_ptx(instr, &type_map)));
+            actual.push_str(&format!(
+                "    {}\n",
+                to_ptx(instr, &type_map, &spaces, &layout)
+            ));
         }
         actual.push_str("}\n\n");
     }
@@ -0,0 +1,97 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashMap;
+
+use ir_model::Type;
+use ptx_backend::compile_llvm_to_ptx;
+use ptx_backend::data_layout::DataLayout;
+
+const GEP_LL: &str = r#"
+%struct.particle = type { i8, double, [3 x float] }
+
+define void @gep(%struct.particle* %p, double* %d, i64 %n, i32 %i, i16 %h, i8 %b) {
+entry:
+  %field = getelementptr %struct.particle, %struct.particle* %p, i64 1, i32 2, i32 1
+  %elem = getelementptr %struct.particle, %struct.particle* %p, i32 %i, i32 2, i64 %n
+  %wide = getelementptr double, double* %d, i64 %n
+  %short = getelementptr double, double* %d, i16 %h
+  %byte = getelementptr double, double* %d, i8 %b
+  %back = getelementptr double, double* %d, i64 -2
+  store double 1.0, double* %wide
+  store double 2.0, double* %short
+  store double 2.5, double* %byte
+  store double 3.0, double* %back
+  store float 4.0, float* %field
+  store float 5.0, float* %elem
+  ret void
+}
+"#;
+
+#[test]
+fn struct_layout_follows_the_data_layout() {
+    let particle = Type::Struct {
+        fields: vec![
+            Type::Int(8),
+            Type::Double,
+            Type::Array {
+                elem: Box::new(Type::Float),
+                len: 3,
+            },
+        ],
+        packed: false,
+    };
+    let layout = DataLayout::new(
+        "",
+        HashMap::from([("struct.particle".to_string(), particle)]),
+    );
+    let named = Type::NamedStruct("struct.particle".into());
+
+    assert_eq!(layout.field_offset(&named, 1), Some(8));
+    assert_eq!(layout.field_offset(&named, 2), Some(16));
+    assert_eq!(layout.size_of(&named), 32);
+    assert_eq!(layout.align_of(&named), 8);
+    assert_eq!(layout.size_of(&Type::Int(64)), 8);
+    assert_eq!(layout.align_of(&Type::Int(64)), 8);
+}
+
+#[test]
+fn gep_uses_64_bit_address_arithmetic() {
+    let ptx = compile_llvm_to_ptx(GEP_LL).expect("Compilation failed");
+
+    // 32 bytes per particle + 16 for the array + 4 for element 1.
+    assert!(ptx.contains("add.s64 %field, %p, 52;"), "{ptx}");
+    assert!(ptx.contains("mad.wide.s32 %elem, %i, 32, %p;"), "{ptx}");
+    assert!(ptx.contains("mad.lo.s64 %elem, %n, 4, %elem;"), "{ptx}");
+    assert!(ptx.contains("add.s64 %elem, %elem, 16;"), "{ptx}");
+    assert!(ptx.contains("mad.lo.s64 %wide, %n, 8, %d;"), "{ptx}");
+    assert!(ptx.contains("cvt.s64.s16 %short$idx, %h;"), "{ptx}");
+    assert!(
+        ptx.contains("mad.lo.s64 %short, %short$idx, 8, %d;"),
+        "{ptx}"
+    );
+    assert!(ptx.contains("cvt.s64.s8 %byte$idx, %b;"), "{ptx}");
+    assert!(
+        ptx.lines()
+            .any(|l| l.starts_with(".reg .s64") && l.contains("%short$idx")),
+        "{ptx}"
+    );
+    assert!(ptx.contains("add.s64 %back, %d, -16;"), "{ptx}");
+    assert!(
+        ptx.contains("st.global.f64 [%wide], 0d3FF0000000000000;"),
+        "{ptx}"
+    );
+}
@@ -124,3 +124,108 @@ fn golden_call_with_args() {
 fn golden_multi_fn_call() {
     check_golden("multi_fn_call");
 }
+
+#[test]
+fn golden_thread_index() {
+    check_golden("thread_index");
+}
+
+#[test]
+fn golden_barriers() {
+    check_golden("barriers");
+}
+
+#[test]
+fn golden_atomics() {
+    check_golden("atomics");
+}
+
+#[test]
+fn golden_bitwise() {
+    check_golden("bitwise");
+}
+
+#[test]
+fn golden_casts() {
+    check_golden("casts");
+}
+
+#[test]
+fn golden_switch() {
+    check_golden("switch");
+}
+
+#[test]
+fn golden_ret_value() {
+    check_golden("ret_value");
+}
+
+#[test]
+fn golden_call_abi() {
+    check_golden("call_abi");
+}
+
+#[test]
+fn golden_indirect_call() {
+    check_golden("indirect_call");
+}
+
+#[test]
+fn golden_printf() {
+    check_golden("printf");
+}
+
+#[test]
+fn golden_globals() {
+    check_golden("globals");
+}
+
+#[test]
+fn golden_frame() {
+    check_golden("frame");
+}
+
+#[test]
+fn golden_arith_i64() {
+    check_golden("arith_i64");
+}
+
+#[test]
+fn golden_arith_f64() {
+    check_golden("arith_f64");
+}
+
+#[test]
+fn golden_icmp_unsigned() {
+    check_golden("icmp_unsigned");
+}
+
+#[test]
+fn golden_fences() {
+    check_golden("fences");
+}
+
+#[test]
+fn golden_switch_narrow() {
+    check_golden("switch_narrow");
+}
+
+#[test]
+fn golden_switch_shared() {
+    check_golden("switch_shared");
+}
+
+#[test]
+fn golden_call_divergent() {
+    check_golden("call_divergent");
+}
+
+#[test]
+fn golden_narrow_memory() {
+    check_golden("narrow_memory");
+}
+
+#[test]
+fn golden_byval_escape() {
+    check_golden("byval_escape");
+}
@@ -0,0 +1,8 @@
+define void @add(i32* %a, i32* %b, i32* %out) {
+entry:
+  %a_val = load i32, i32* %a, align 4
+  %b_val = load i32, i32* %b, align 4
+  %sum = add i32 %a_val, %b_val
+  store i32 %sum, i32* %out, align 4
+  ret void
+}
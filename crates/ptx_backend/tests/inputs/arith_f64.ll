@@ -0,0 +1,9 @@
+define double @arith_f64(double %a, double %b) {
+entry:
+  %sum = fadd double %a, %b
+  %prod = fmul double %sum, %b
+  %quot = fdiv double %prod, %a
+  %lt = fcmp olt double %quot, %b
+  %res = select i1 %lt, double %quot, double %b
+  ret double %res
+}
@@ -0,0 +1,8 @@
+define i64 @arith_i64(i64 %a, i64 %b) {
+entry:
+  %sum = add i64 %a, %b
+  %prod = mul i64 %sum, %b
+  %quot = udiv i64 %prod, %a
+  %rem = srem i64 %quot, %b
+  ret i64 %rem
+}
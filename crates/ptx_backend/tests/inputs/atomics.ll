@@ -0,0 +1,16 @@
+define void @atomics(i32* %counter, float* %sum, i64* %big, i32* %lock, i32* %old_out, i32 %v) {
+entry:
+  %old = atomicrmw add i32* %counter, i32 1 monotonic
+  store i32 %old, i32* %old_out, align 4
+  %unused = atomicrmw fadd float* %sum, float 1.5 monotonic
+  %m = atomicrmw max i32* %counter, i32 %v acquire
+  %u = atomicrmw umin i64* %big, i64 7 release
+  %s = atomicrmw sub i32* %counter, i32 %v monotonic
+  %x = atomicrmw xchg i32* %lock, i32 0 seq_cst
+  %pair = cmpxchg i32* %lock, i32 0, i32 1 acq_rel monotonic
+  %prev = extractvalue { i32, i1 } %pair, 0
+  %ok = extractvalue { i32, i1 } %pair, 1
+  %r = select i1 %ok, i32 %prev, i32 %m
+  store i32 %r, i32* %old_out, align 4
+  ret void
+}
@@ -0,0 +1,21 @@
+declare void @llvm.nvvm.barrier0()
+declare void @llvm.nvvm.barrier.sync.cnt(i32, i32)
+declare void @llvm.nvvm.barrier.cta.arrive.aligned.count(i32, i32)
+declare void @llvm.nvvm.membar.cta()
+declare void @llvm.nvvm.membar.gl()
+declare void @llvm.nvvm.membar.sys()
+
+define void @barriers(i32* %out, i32 %n) {
+entry:
+  store i32 1, i32* %out, align 4
+  call void @llvm.nvvm.barrier0()
+  call void @llvm.nvvm.barrier.sync.cnt(i32 1, i32 %n)
+  call void @llvm.nvvm.barrier.cta.arrive.aligned.count(i32 2, i32 64)
+  call void @llvm.nvvm.membar.cta()
+  call void @llvm.nvvm.membar.gl()
+  call void @llvm.nvvm.membar.sys()
+  fence acq_rel
+  fence syncscope("singlethread") acquire
+  fence seq_cst
+  ret void
+}
@@ -0,0 +1,5 @@
+define float @bitcast(i32 %a) {
+entry:
+  %f = bitcast i32 %a to float
+  ret float %f
+}
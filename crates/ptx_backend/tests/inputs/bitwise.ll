@@ -0,0 +1,20 @@
+define void @bitwise(i32* %out, i64* %out64, i32 %a, i32 %b, i64 %c, i64 %n, i1 %p, i1 %q) {
+entry:
+  %and = and i32 %a, %b
+  %or = or i32 %and, 255
+  %xor = xor i32 %or, %a
+  %not = xor i32 %xor, -1
+  %shl = shl i32 %not, 3
+  %lshr = lshr i32 %shl, %b
+  %ashr = ashr i32 %lshr, 1
+  store i32 %ashr, i32* %out, align 4
+  %wide = shl i64 %c, %n
+  %hi = lshr i64 %wide, 32
+  store i64 %hi, i64* %out64, align 8
+  %both = and i1 %p, %q
+  %either = or i1 %both, %p
+  %neither = xor i1 %either, true
+  %sel = select i1 %neither, i32 %a, i32 %b
+  store i32 %sel, i32* %out, align 4
+  ret void
+}
@@ -0,0 +1,10 @@
+define void @br_cond(i1 %c) {
+entry:
+  br i1 %c, label %then, label %else
+
+then:
+  ret void
+
+else:
+  ret void
+}
@@ -0,0 +1,7 @@
+define void @br_uncond() {
+entry:
+  br label %exit
+
+exit:
+  ret void
+}
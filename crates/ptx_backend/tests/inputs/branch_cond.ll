@@ -0,0 +1,12 @@
+define void @branch_cond(i32 %a, i32 %b) {
+entry:
+  %c = icmp slt i32 %a, %b
+  br i1 %c, label %then, label %exit
+
+then:
+  %s = add i32 %a, %b
+  br label %exit
+
+exit:
+  ret void
+}
@@ -0,0 +1,11 @@
+define void @branch_uncond(i32 %a) {
+entry:
+  br label %next
+
+next:
+  %s = add i32 %a, 1
+  br label %exit
+
+exit:
+  ret void
+}
@@ -0,0 +1,15 @@
+%struct.pair = type { float, i32 }
+
+define i32 @second(%struct.pair* %p) {
+entry:
+  %f = getelementptr inbounds %struct.pair, %struct.pair* %p, i32 0, i32 1
+  %v = load i32, i32* %f, align 4
+  ret i32 %v
+}
+
+define void @kernel(%struct.pair* byval(%struct.pair) align 4 %pair, i32 addrspace(1)* %out) {
+entry:
+  %v = call i32 @second(%struct.pair* %pair)
+  store i32 %v, i32 addrspace(1)* %out, align 4
+  ret void
+}
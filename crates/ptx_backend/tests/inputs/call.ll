@@ -0,0 +1,10 @@
+define void @foo() {
+entry:
+  ret void
+}
+
+define void @main() {
+entry:
+  call void @foo()
+  ret void
+}
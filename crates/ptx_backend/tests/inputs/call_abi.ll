@@ -0,0 +1,31 @@
+%struct.pair = type { float, i32 }
+
+define float @scale(float %x, i8 %k, i1 %neg) {
+entry:
+  %c = sitofp i8 %k to float
+  %y = fmul float %x, %c
+  %m = fsub float 0.000000e+00, %y
+  %r = select i1 %neg, float %m, float %y
+  ret float %r
+}
+
+define i1 @positive(%struct.pair* byval(%struct.pair) align 4 %p) {
+entry:
+  %f = getelementptr inbounds %struct.pair, %struct.pair* %p, i32 0, i32 1
+  %v = load i32, i32* %f, align 4
+  %b = icmp sgt i32 %v, 0
+  ret i1 %b
+}
+
+define void @kernel(float* %out, i32 %n) {
+entry:
+  %pair = alloca %struct.pair, align 4
+  %first = getelementptr inbounds %struct.pair, %struct.pair* %pair, i32 0, i32 0
+  store float 2.000000e+00, float* %first, align 4
+  %second = getelementptr inbounds %struct.pair, %struct.pair* %pair, i32 0, i32 1
+  store i32 %n, i32* %second, align 4
+  %pos = call i1 @positive(%struct.pair* byval(%struct.pair) align 4 %pair)
+  %s = call float @scale(float 1.500000e+00, i8 3, i1 %pos)
+  store float %s, float* %out, align 4
+  ret void
+}
@@ -0,0 +1,23 @@
+declare i32 @llvm.nvvm.read.ptx.sreg.tid.x()
+
+define void @helper(i32* %out, i32 %v) {
+entry:
+  store i32 %v, i32* %out, align 4
+  ret void
+}
+
+define void @call_divergent(i32* %out, i32 %n) {
+entry:
+  call void @helper(i32* %out, i32 0)
+  %tid = call i32 @llvm.nvvm.read.ptx.sreg.tid.x()
+  %in = icmp ult i32 %tid, %n
+  br i1 %in, label %then, label %done
+
+then:
+  call void @helper(i32* %out, i32 %tid)
+  br label %done
+
+done:
+  call void @helper(i32* %out, i32 1)
+  ret void
+}
@@ -0,0 +1,12 @@
+define void @foo(i32 %a, float %b) {
+entry:
+  ret void
+}
+
+define void @main(i32* %p, float* %q) {
+entry:
+  %i = load i32, i32* %p, align 4
+  %val = load float, float* %q, align 4
+  call void @foo(i32 %i, float %val)
+  ret void
+}
@@ -0,0 +1,23 @@
+define void @casts(i8 %b, i16 %h, i32 %i, i64 %l, float %f, double %d, i1 %p, float* %ptr, i64* %out) {
+entry:
+  %sext = sext i8 %b to i32
+  %zext16 = zext i16 %h to i64
+  %zextp = zext i1 %p to i32
+  %sextp = sext i1 %p to i64
+  %trunc = trunc i64 %l to i16
+  %bit = trunc i32 %i to i1
+  %fptosi = fptosi float %f to i32
+  %fptoui = fptoui double %d to i64
+  %sitofp = sitofp i32 %i to double
+  %uitofp = uitofp i64 %l to float
+  %fpext = fpext float %f to double
+  %fptrunc = fptrunc double %d to float
+  %ptrtoint = ptrtoint float* %ptr to i64
+  %inttoptr = inttoptr i64 %l to i32*
+  %narrow = ptrtoint float* %ptr to i32
+  %asint = bitcast float %f to i32
+  %asdouble = bitcast i64 %l to double
+  %cast = bitcast float* %ptr to i8*
+  store i64 %ptrtoint, i64* %out, align 8
+  ret void
+}
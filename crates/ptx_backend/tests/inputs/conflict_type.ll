@@ -0,0 +1,8 @@
+define void @main() {
+entry:
+  %x = alloca i32, align 4
+  store i32 1, i32* %x, align 4
+  %val = load i32, i32* %x, align 4
+  %cast = bitcast i32 %val to float
+  ret void
+}
@@ -0,0 +1,7 @@
+define i32 @div(i32 %a, i32 %b, float %x, float %y) {
+entry:
+  %q = sdiv i32 %a, %b
+  %u = udiv i32 %a, %b
+  %f = fdiv float %x, %y
+  ret i32 %q
+}
@@ -0,0 +1,30 @@
+define float @dot(float* %x, float* %y, i32 %n) {
+entry:
+  %i = alloca i32, align 4
+  %sum = alloca float, align 4
+  store i32 0, i32* %i, align 4
+  store float 0.0, float* %sum, align 4
+  br label %loop
+
+loop:
+  %idx = load i32, i32* %i, align 4
+  %cond = icmp slt i32 %idx, %n
+  br i1 %cond, label %body, label %exit
+
+body:
+  %x_ptr = getelementptr float, float* %x, i32 %idx
+  %y_ptr = getelementptr float, float* %y, i32 %idx
+  %xval = load float, float* %x_ptr, align 4
+  %yval = load float, float* %y_ptr, align 4
+  %prod = fmul float %xval, %yval
+  %acc = load float, float* %sum, align 4
+  %new_sum = fadd float %acc, %prod
+  store float %new_sum, float* %sum, align 4
+  %next = add i32 %idx, 1
+  store i32 %next, i32* %i, align 4
+  br label %loop
+
+exit:
+  %result = load float, float* %sum, align 4
+  ret float %result
+}
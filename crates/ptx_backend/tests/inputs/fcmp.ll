@@ -0,0 +1,6 @@
+define i1 @fcmp(float %x, float %y) {
+entry:
+  %olt = fcmp olt float %x, %y
+  %oeq = fcmp oeq float %x, %y
+  ret i1 %olt
+}
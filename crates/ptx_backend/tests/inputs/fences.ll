@@ -0,0 +1,10 @@
+define void @fences(i32* %p, i32 %v) {
+entry:
+  fence acq_rel
+  fence syncscope("device") acq_rel
+  fence syncscope("block") release
+  fence syncscope("device") seq_cst
+  %old = atomicrmw add i32* %p, i32 %v syncscope("device") monotonic
+  %pair = cmpxchg i32* %p, i32 %old, i32 %v syncscope("block") acquire monotonic
+  ret void
+}
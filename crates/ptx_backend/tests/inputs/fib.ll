@@ -0,0 +1,30 @@
+define void @fib(i32* %out, i32 %n) {
+entry:
+  %a = alloca i32, align 4
+  %b = alloca i32, align 4
+  %i = alloca i32, align 4
+  store i32 0, i32* %a, align 4
+  store i32 1, i32* %b, align 4
+  store i32 0, i32* %i, align 4
+  br label %loop
+
+loop:
+  %idx = load i32, i32* %i, align 4
+  %cond = icmp slt i32 %idx, %n
+  br i1 %cond, label %body, label %exit
+
+body:
+  %aval = load i32, i32* %a, align 4
+  %out_ptr = getelementptr i32, i32* %out, i32 %idx
+  store i32 %aval, i32* %out_ptr, align 4
+  %bval = load i32, i32* %b, align 4
+  %sum = add i32 %aval, %bval
+  store i32 %bval, i32* %a, align 4
+  store i32 %sum, i32* %b, align 4
+  %next = add i32 %idx, 1
+  store i32 %next, i32* %i, align 4
+  br label %loop
+
+exit:
+  ret void
+}
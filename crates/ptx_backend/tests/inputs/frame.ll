@@ -0,0 +1,22 @@
+declare void @fill(i32*)
+
+define void @stage(float* %out, i32 %n) {
+entry:
+  %n.addr = alloca i32, align 4
+  %tmp = alloca i32, align 4
+  %buf = alloca [4 x float], align 16
+  store i32 %n, i32* %n.addr, align 4
+  %x = load i32, i32* %n.addr, align 4
+  store i32 %x, i32* %tmp, align 4
+  call void @fill(i32* %tmp)
+  %y = load i32, i32* %tmp, align 4
+  %p = getelementptr inbounds [4 x float], [4 x float]* %buf, i64 0, i64 1
+  %f = sitofp i32 %y to float
+  store float %f, float* %p, align 4
+  %g = load float, float* %p, align 4
+  store float %g, float* %out, align 4
+  ret void
+}
+
+!nvvm.annotations = !{!0}
+!0 = !{void (float*, i32)* @stage, !"kernel", i32 1}
@@ -0,0 +1,29 @@
+%struct.params = type { i32, float, [2 x i16] }
+
+@coeffs = addrspace(4) constant [4 x float] [float 1.000000e+00, float 5.000000e-01, float 2.500000e-01, float 1.250000e-01], align 4
+@lut = addrspace(1) global [2 x [3 x i32]] [[3 x i32] [i32 1, i32 2, i32 3], [3 x i32] zeroinitializer], align 16
+@counter = addrspace(1) global i32 0, align 4
+@config = addrspace(1) global %struct.params { i32 7, float 2.000000e+00, [2 x i16] [i16 -1, i16 3] }, align 4
+@tables = addrspace(1) global [2 x i32*] [i32* getelementptr inbounds ([2 x [3 x i32]], [2 x [3 x i32]]* addrspacecast ([2 x [3 x i32]] addrspace(1)* @lut to [2 x [3 x i32]]*), i64 0, i64 0, i64 0), i32* addrspacecast (i32 addrspace(1)* @counter to i32*)], align 8
+@tile = internal addrspace(3) global [64 x float] undef, align 4
+
+define void @smooth(float* %out, i32 %i) {
+entry:
+  %idx = sext i32 %i to i64
+  %c = getelementptr inbounds [4 x float], [4 x float] addrspace(4)* @coeffs, i64 0, i64 %idx
+  %w = load float, float addrspace(4)* %c, align 4
+  %s = getelementptr inbounds [64 x float], [64 x float]* addrspacecast ([64 x float] addrspace(3)* @tile to [64 x float]*), i64 0, i64 %idx
+  store float %w, float* %s, align 4
+  %n = load i32, i32 addrspace(1)* @counter, align 4
+  %n1 = add i32 %n, 1
+  store i32 %n1, i32 addrspace(1)* @counter, align 4
+  %t = load i32*, i32** addrspacecast (i32* addrspace(1)* getelementptr inbounds ([2 x i32*], [2 x i32*] addrspace(1)* @tables, i64 0, i64 0) to i32**), align 8
+  %v = load i32, i32* %t, align 4
+  %f = sitofp i32 %v to float
+  %o = getelementptr inbounds float, float* %out, i64 %idx
+  store float %f, float* %o, align 4
+  ret void
+}
+
+!nvvm.annotations = !{!0}
+!0 = !{void (float*, i32)* @smooth, !"kernel", i32 1}
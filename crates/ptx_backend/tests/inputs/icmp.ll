@@ -0,0 +1,7 @@
+define i1 @icmp(i32 %a, i32 %b) {
+entry:
+  %eq = icmp eq i32 %a, %b
+  %lt = icmp slt i32 %a, %b
+  %uge = icmp uge i32 %a, %b
+  ret i1 %lt
+}
@@ -0,0 +1,11 @@
+define i1 @icmp_unsigned(i64 %a, i64 %b, i16 %c, i16 %d) {
+entry:
+  %ult = icmp ult i64 %a, %b
+  %ugt = icmp ugt i64 %a, %b
+  %ule = icmp ule i16 %c, %d
+  %uge = icmp uge i16 %c, %d
+  %x = and i1 %ult, %ugt
+  %y = or i1 %ule, %uge
+  %res = xor i1 %x, %y
+  ret i1 %res
+}
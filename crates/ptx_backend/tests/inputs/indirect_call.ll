@@ -0,0 +1,24 @@
+define float @twice(float %x) {
+entry:
+  %r = fmul float %x, 2.000000e+00
+  ret float %r
+}
+
+define float @square(float %x) {
+entry:
+  %r = fmul float %x, %x
+  ret float %r
+}
+
+define void @apply(float* %data, i1 %sq, void (float*)* %done) {
+entry:
+  %fn = select i1 %sq, float (float)* @square, float (float)* @twice
+  %v = load float, float* %data, align 4
+  %r = call float %fn(float %v)
+  store float %r, float* %data, align 4
+  call void %done(float* %data)
+  ret void
+}
+
+!nvvm.annotations = !{!0}
+!0 = !{void (float*, i1, void (float*)*)* @apply, !"kernel", i32 1}
@@ -0,0 +1,4 @@
+define void @minimal_ret() {
+entry:
+  ret void
+}
@@ -0,0 +1,12 @@
+define i32 @square(i32 %x) {
+entry:
+  %r = mul i32 %x, %x
+  ret i32 %r
+}
+
+define void @kernel(i32* %out, i32 %v) {
+entry:
+  %s = call i32 @square(i32 %v)
+  store i32 %s, i32* %out, align 4
+  ret void
+}
@@ -0,0 +1,11 @@
+define void @narrow_memory(i8 addrspace(1)* %bytes, i1 addrspace(1)* %flags) {
+entry:
+  %b = load i8, i8 addrspace(1)* %bytes
+  %c = add i8 %b, 1
+  store i8 %c, i8 addrspace(1)* %bytes
+  %f = load i1, i1 addrspace(1)* %flags
+  %n = xor i1 %f, true
+  store i1 %n, i1 addrspace(1)* %flags
+  store i1 true, i1 addrspace(1)* %flags
+  ret void
+}
@@ -0,0 +1,16 @@
+define i32 @phi(i1 %c, i32 %a, i32 %b) {
+entry:
+  br i1 %c, label %left, label %right
+
+left:
+  %l = add i32 %a, 1
+  br label %join
+
+right:
+  %r = add i32 %b, 2
+  br label %join
+
+join:
+  %v = phi i32 [ %l, %left ], [ %r, %right ]
+  ret i32 %v
+}
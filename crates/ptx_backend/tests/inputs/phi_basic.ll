@@ -0,0 +1,14 @@
+define i32 @main(i1 %c) {
+entry:
+  br i1 %c, label %a, label %b
+
+a:
+  br label %merge
+
+b:
+  br label %merge
+
+merge:
+  %val = phi i32 [ 1, %a ], [ 2, %b ]
+  ret i32 %val
+}
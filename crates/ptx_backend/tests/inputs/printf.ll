@@ -0,0 +1,15 @@
+@.str = private unnamed_addr constant [22 x i8] c"i=%d x=%f c=%d n=%ld\0A\00", align 1
+@.str.1 = private unnamed_addr constant [7 x i8] c"hello\0A\00", align 1
+@.str.2 = private unnamed_addr constant [14 x i8] c"%hhd %hd %hd\0A\00", align 1
+
+declare i32 @printf(i8*, ...)
+
+define void @debug(float* %data, i64 %n, i8 %c, i16 %s) {
+entry:
+  %x = load float, float* %data, align 4
+  %i = fptosi float %x to i32
+  %r = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([22 x i8], [22 x i8]* @.str, i64 0, i64 0), i32 %i, float %x, i8 %c, i64 %n)
+  %h = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([7 x i8], [7 x i8]* @.str.1, i64 0, i64 0))
+  %k = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([14 x i8], [14 x i8]* @.str.2, i64 0, i64 0), i8 -1, i16 -2, i16 %s)
+  ret void
+}
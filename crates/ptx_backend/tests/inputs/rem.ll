@@ -0,0 +1,6 @@
+define i32 @rem(i32 %a, i32 %b) {
+entry:
+  %s = srem i32 %a, %b
+  %u = urem i32 %a, %b
+  ret i32 %s
+}
@@ -0,0 +1,3 @@
+define void @ret_only() {
+  ret void
+}
@@ -0,0 +1,23 @@
+define i32 @add(i32 %a, i32 %b) {
+entry:
+  %sum = add i32 %a, %b
+  ret i32 %sum
+}
+
+define i1 @is_zero(i32 %a) {
+entry:
+  %z = icmp eq i32 %a, 0
+  ret i1 %z
+}
+
+define float @half() {
+entry:
+  ret float 5.000000e-01
+}
+
+define void @kernel(i32* %out, i32 %x) {
+entry:
+  %sum = call i32 @add(i32 %x, i32 1)
+  store i32 %sum, i32* %out, align 4
+  ret void
+}
@@ -0,0 +1,5 @@
+define i32 @select(i1 %c, i32 %a, i32 %b) {
+entry:
+  %r = select i1 %c, i32 %a, i32 %b
+  ret i32 %r
+}
@@ -0,0 +1,5 @@
+define i32 @sub(i32 %a, i32 %b) {
+entry:
+  %r = sub i32 %a, %b
+  ret i32 %r
+}
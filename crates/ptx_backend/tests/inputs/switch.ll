@@ -0,0 +1,52 @@
+define void @switch_kernel(i32* %out, i32 %x, i64 %y) {
+entry:
+  switch i32 %x, label %other [
+    i32 1, label %one
+    i32 2, label %two
+    i32 3, label %three
+    i32 5, label %five
+  ]
+
+one:
+  store i32 10, i32* %out, align 4
+  br label %sparse
+
+two:
+  store i32 20, i32* %out, align 4
+  br label %sparse
+
+three:
+  store i32 30, i32* %out, align 4
+  br label %sparse
+
+five:
+  store i32 50, i32* %out, align 4
+  br label %sparse
+
+other:
+  store i32 0, i32* %out, align 4
+  br label %sparse
+
+sparse:
+  switch i64 %y, label %done [
+    i64 0, label %small
+    i64 1000, label %big
+  ]
+
+small:
+  store i32 1, i32* %out, align 4
+  br label %done
+
+big:
+  store i32 2, i32* %out, align 4
+  br label %done
+
+done:
+  ret void
+}
+
+define void @never(i32* %out) {
+entry:
+  store i32 0, i32* %out, align 4
+  unreachable
+}
@@ -0,0 +1,27 @@
+define void @switch_narrow(i32* %out, i8 %x, i16 %y) {
+entry:
+  switch i8 %x, label %second [
+    i8 1, label %one
+    i8 -1, label %minus_one
+  ]
+
+one:
+  store i32 1, i32* %out, align 4
+  br label %second
+
+minus_one:
+  store i32 -1, i32* %out, align 4
+  br label %second
+
+second:
+  switch i16 %y, label %done [
+    i16 7, label %seven
+  ]
+
+seven:
+  store i32 7, i32* %out, align 4
+  br label %done
+
+done:
+  ret void
+}
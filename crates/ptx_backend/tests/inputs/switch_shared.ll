@@ -0,0 +1,52 @@
+define void @switch_shared(i32* %out, i32 %x) {
+entry:
+  switch i32 %x, label %done [
+    i32 0, label %a
+    i32 1, label %b
+    i32 2, label %c
+    i32 3, label %d
+  ]
+
+a:
+  store i32 10, i32* %out, align 4
+  br label %again
+
+b:
+  store i32 11, i32* %out, align 4
+  br label %again
+
+c:
+  store i32 12, i32* %out, align 4
+  br label %again
+
+d:
+  store i32 13, i32* %out, align 4
+  br label %again
+
+again:
+  switch i32 %x, label %done [
+    i32 0, label %e
+    i32 1, label %f
+    i32 2, label %g
+    i32 3, label %h
+  ]
+
+e:
+  store i32 20, i32* %out, align 4
+  br label %done
+
+f:
+  store i32 21, i32* %out, align 4
+  br label %done
+
+g:
+  store i32 22, i32* %out, align 4
+  br label %done
+
+h:
+  store i32 23, i32* %out, align 4
+  br label %done
+
+done:
+  ret void
+}
@@ -0,0 +1,21 @@
+declare i32 @llvm.nvvm.read.ptx.sreg.tid.x()
+declare i32 @llvm.nvvm.read.ptx.sreg.ctaid.x()
+declare i32 @llvm.nvvm.read.ptx.sreg.ntid.x()
+declare i32 @llvm.nvvm.read.ptx.sreg.laneid()
+declare i64 @llvm.nvvm.read.ptx.sreg.clock64()
+
+define void @thread_index(i32* %out, i64* %clk) {
+entry:
+  %tid = call i32 @llvm.nvvm.read.ptx.sreg.tid.x()
+  %ctaid = call i32 @llvm.nvvm.read.ptx.sreg.ctaid.x()
+  %ntid = call i32 @llvm.nvvm.read.ptx.sreg.ntid.x()
+  %base = mul i32 %ctaid, %ntid
+  %i = add i32 %base, %tid
+  %lane = call i32 @llvm.nvvm.read.ptx.sreg.laneid()
+  %v = add i32 %i, %lane
+  %p = getelementptr i32, i32* %out, i32 %i
+  store i32 %v, i32* %p, align 4
+  %t = call i64 @llvm.nvvm.read.ptx.sreg.clock64()
+  store i64 %t, i64* %clk, align 8
+  ret void
+}
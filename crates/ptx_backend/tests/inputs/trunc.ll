@@ -0,0 +1,5 @@
+define i8 @trunc(i32 %a) {
+entry:
+  %r = trunc i32 %a to i8
+  ret i8 %r
+}
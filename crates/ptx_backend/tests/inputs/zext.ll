@@ -0,0 +1,5 @@
+define i32 @zext(i8 %a) {
+entry:
+  %r = zext i8 %a to i32
+  ret i32 %r
+}
@@ -0,0 +1,116 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use ptx_backend::module::{CodegenOptions, FunctionKind};
+use ptx_backend::{compile_llvm_to_ptx, compile_module};
+
+const HELPERS_LL: &str = r#"
+define internal float @scale(float %x) {
+entry:
+  %y = fmul float %x, 2.0
+  ret float %y
+}
+
+define float @unused(float %x) {
+entry:
+  ret float %x
+}
+
+declare i32 @external_helper(i32)
+
+define void @first(float* %out) {
+entry:
+  %v = call float @scale(float 1.0)
+  store float %v, float* %out
+  ret void
+}
+
+define void @second(i32* %out) {
+entry:
+  %v = call i32 @external_helper(i32 3)
+  store i32 %v, i32* %out
+  ret void
+}
+
+!nvvm.annotations = !{!0}
+!0 = !{void (float*)* @first, !"kernel", i32 1}
+"#;
+
+#[test]
+fn nvvm_annotations_select_the_kernels() {
+    assert_eq!(llvm_parser::nvvm_kernels(HELPERS_LL), vec!["first"]);
+
+    let ptx = compile_llvm_to_ptx(HELPERS_LL).unwrap();
+
+    assert!(ptx.contains(".visible .entry first("), "{ptx}");
+    assert!(ptx.contains(".visible .func second("), "{ptx}");
+    assert!(
+        ptx.contains(".func (.param .f32 func_retval0) scale("),
+        "{ptx}"
+    );
+    assert!(!ptx.contains(".visible .func (.param .f32 func_retval0) scale("));
+    assert!(
+        ptx.contains(".extern .func (.param .u32 func_retval0) external_helper("),
+        "{ptx}"
+    );
+    assert_eq!(ptx.matches(".entry").count(), 1, "{ptx}");
+}
+
+#[test]
+fn explicit_kernel_list_and_fallback() {
+    let module = llvm_parser::parse_llvm_ir_from_str(HELPERS_LL).unwrap();
+
+    // Without annotations, uncalled void functions are the kernels.
+    let ptx = compile_module(&module, &CodegenOptions::default()).unwrap();
+    let kinds: Vec<(&str, FunctionKind)> = ptx
+        .functions
+        .iter()
+        .map(|f| (f.name.as_str(), f.kind))
+        .collect();
+    assert!(kinds.contains(&("first", FunctionKind::Entry)));
+    assert!(kinds.contains(&("second", FunctionKind::Entry)));
+    assert!(kinds.contains(&("scale", FunctionKind::Func)));
+    assert!(kinds.contains(&("unused", FunctionKind::Func)));
+
+    let options = CodegenOptions {
+        kernels: vec!["second".to_string()],
+        ..Default::default()
+    };
+    let ptx = compile_module(&module, &options).unwrap().to_string();
+    assert!(ptx.contains(".visible .entry second("), "{ptx}");
+    assert!(ptx.contains(".visible .func first("), "{ptx}");
+}
+
+#[test]
+fn ptx_kernel_calling_convention_marks_kernels() {
+    let ptx = compile_llvm_to_ptx(
+        r#"
+define ptx_kernel void @k() {
+entry:
+  ret void
+}
+
+define void @helper() {
+entry:
+  ret void
+}
+"#,
+    )
+    .unwrap();
+
+    assert!(ptx.contains(".visible .entry k("), "{ptx}");
+    assert!(ptx.contains(".visible .func helper("), "{ptx}");
+}
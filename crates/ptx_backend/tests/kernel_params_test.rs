@@ -0,0 +1,48 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ptx_backend::compile_llvm_to_ptx;
+
+const SAXPY_LL: &str = include_str!("inputs/saxpy.ll");
+
+#[test]
+fn saxpy_declares_and_loads_params() {
+    let ptx = compile_llvm_to_ptx(SAXPY_LL).expect("Compilation failed");
+
+    assert!(
+        ptx.contains(".entry saxpy("),
+        "missing parameter list:\n{ptx}"
+    );
+    assert!(ptx.contains(".param .f32 saxpy_param_0,"));
+    assert!(ptx.contains(".param .u64 saxpy_param_1,"));
+    assert!(ptx.contains(".param .u32 saxpy_param_4\n"));
+
+    assert!(ptx.contains("ld.param.f32 %a, [saxpy_param_0];"));
+    assert!(ptx.contains("ld.param.u64 %x, [saxpy_param_1];"));
+    assert!(ptx.contains("cvta.to.global.u64 %x, %x;"));
+    assert!(ptx.contains("ld.param.u32 %n, [saxpy_param_4];"));
+}
+
+#[test]
+fn bool_params_become_predicates() {
+    let ptx = compile_llvm_to_ptx(include_str!("inputs/br_cond.ll")).expect("Compilation failed");
+
+    assert!(ptx.contains(".param .u8 br_cond_param_0"));
+    assert!(ptx.contains("ld.param.u8 %c$param, [br_cond_param_0];"));
+    assert!(ptx.contains("setp.ne.s16 %c, %c$param, 0;"));
+    assert!(ptx.contains(".reg .pred %c;"));
+}
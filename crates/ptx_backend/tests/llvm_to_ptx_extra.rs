@@ -15,10 +15,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use ir_model::Instruction;
-use llvm_parser::convert::lower;
+use ir_model::{BasicBlock, CallingConv, Function};
+use llvm_parser::convert::{lower, name_to_string};
 use llvm_parser::parse_module::parse_module;
+use ptx_backend::data_layout::DataLayout;
 use ptx_backend::lower_function;
+use ptx_backend::module::{CodegenOptions, PtxModule};
 
 fn run_test(filename: &str) -> String {
     let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -28,28 +30,42 @@ fn run_test(filename: &str) -> String {
 
     let module = parse_module(&path).expect("Failed to parse module");
 
-    let mut output = String::new();
+    let mut ptx = PtxModule::default();
+
+    let layout = DataLayout::from_module(&module);
 
     for func in module.functions {
-        let all_instrs: Vec<(String, Vec<Instruction>)> = func
+        let signature = llvm_parser::lower_signature(&func.parameters, &func.return_type);
+        let all_instrs: Vec<BasicBlock> = func
             .basic_blocks
             .into_iter()
             .map(|block| {
                 let instrs = block
                     .instrs
                     .iter()
-                    .map(|instr| lower(&func.name, instr))
+                    .map(|instr| lower(instr, &module.types))
                     .collect::<Vec<_>>();
-                (block.name.to_string(), instrs)
+                BasicBlock::new(name_to_string(&block.name), instrs)
             })
             .collect();
 
-        for line in lower_function(&func.name, &all_instrs, "sm_75") {
-            output.push_str(&format!("{}\n", line));
-        }
+        let function = Function {
+            name: func.name.clone(),
+            signature,
+            attributes: vec![],
+            kernel: true,
+            calling_conv: CallingConv::C,
+            internal: false,
+            blocks: all_instrs,
+        };
+
+        ptx.add_function(
+            lower_function(&function, &[], &layout, &CodegenOptions::default())
+                .expect("lowering failed"),
+        );
     }
 
-    output
+    ptx.to_string()
 }
 
 #[test]
@@ -0,0 +1,83 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use ptx_backend::addr_space::AddressSpace;
+use ptx_backend::ast::{Instruction, VarDecl};
+use ptx_backend::module::{CodegenOptions, FunctionKind, Linkage, PtxFunction, PtxModule};
+use ptx_backend::{compile_llvm_to_ptx, compile_module};
+
+fn function(name: &str, kind: FunctionKind, callees: &[&str]) -> PtxFunction {
+    PtxFunction {
+        name: name.to_string(),
+        kind,
+        linkage: Linkage::Visible,
+        ret: None,
+        params: vec![],
+        body: vec![Instruction::new("ret", &[], vec![]).into()],
+        callees: callees.iter().map(|c| c.to_string()).collect(),
+    }
+}
+
+#[test]
+fn multi_function_module_has_one_header() {
+    let ptx = compile_llvm_to_ptx(include_str!("inputs/multi_fn_call.ll")).unwrap();
+
+    assert_eq!(ptx.matches(".version").count(), 1, "{ptx}");
+    assert_eq!(ptx.matches(".target").count(), 1, "{ptx}");
+    assert_eq!(ptx.matches(".address_size").count(), 1, "{ptx}");
+    assert!(ptx.starts_with(".version 7.0\n.target sm_75\n.address_size 64\n"));
+}
+
+#[test]
+fn target_and_version_are_configurable() {
+    let module = llvm_parser::parse_llvm_ir_from_str(include_str!("inputs/add.ll")).unwrap();
+    let options = CodegenOptions {
+        target: "sm_86".to_string(),
+        ptx_version: "7.8".to_string(),
+        ..Default::default()
+    };
+    let ptx = compile_module(&module, &options).unwrap().to_string();
+
+    assert!(ptx.starts_with(".version 7.8\n.target sm_86\n"), "{ptx}");
+}
+
+#[test]
+fn device_functions_come_first_in_dependency_order() {
+    let mut module = PtxModule::default();
+    module.globals.push(VarDecl {
+        space: AddressSpace::Global,
+        align: Some(4),
+        ty: "b8".to_string(),
+        name: "table".to_string(),
+        len: Some(16),
+        init: vec![],
+    });
+    module.add_function(function("kernel", FunctionKind::Entry, &["outer"]));
+    module.add_function(function("outer", FunctionKind::Func, &["inner"]));
+    module.add_function(function("inner", FunctionKind::Func, &[]));
+    let ptx = module.to_string();
+
+    let pos = |needle: &str| {
+        ptx.find(needle)
+            .unwrap_or_else(|| panic!("`{needle}` missing:\n{ptx}"))
+    };
+    assert!(pos(".address_size") < pos(".global .align 4"));
+    assert!(pos(".global .align 4") < pos(".visible .func inner(\n);"));
+    assert!(pos(".visible .func inner(\n);") < pos(".visible .func outer(\n);"));
+    assert!(pos(".visible .func outer(\n);") < pos("// Function: inner"));
+    assert!(pos("// Function: inner") < pos("// Function: outer"));
+    assert!(pos("// Function: outer") < pos(".entry kernel("));
+}
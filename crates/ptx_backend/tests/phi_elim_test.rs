@@ -0,0 +1,140 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir_model::{BasicBlock, Instruction};
+use llvm_parser::parse_llvm_ir_from_str;
+use ptx_backend::phi_elim::eliminate_phis;
+
+fn lower(ll: &str) -> Vec<BasicBlock> {
+    let module = parse_llvm_ir_from_str(ll).unwrap();
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).unwrap();
+    eliminate_phis(&blocks)
+}
+
+/// `(dst, src)` register names of the copies in `block`, in order.
+fn copies(blocks: &[BasicBlock], block: &str) -> Vec<(String, String)> {
+    let instrs = &blocks.iter().find(|b| b.name == block).unwrap().instrs;
+    instrs
+        .iter()
+        .filter_map(|i| match i {
+            Instruction::Copy { dst, src, .. } => Some((
+                dst.name().unwrap_or_default().to_string(),
+                src.name().unwrap_or_default().to_string(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_phis(blocks: &[BasicBlock]) -> bool {
+    blocks.iter().any(|b| {
+        b.instrs
+            .iter()
+            .any(|i| matches!(i, Instruction::Phi { .. }))
+    })
+}
+
+#[test]
+fn copies_go_to_the_end_of_each_predecessor() {
+    let blocks = lower(include_str!("inputs/phi.ll"));
+
+    assert!(!has_phis(&blocks));
+    assert_eq!(copies(&blocks, "left"), vec![("v".into(), "l".into())]);
+    assert_eq!(copies(&blocks, "right"), vec![("v".into(), "r".into())]);
+
+    let left = &blocks.iter().find(|b| b.name == "left").unwrap().instrs;
+    assert!(matches!(left.last(), Some(Instruction::Br { .. })));
+}
+
+#[test]
+fn swapped_loop_variables_use_a_scratch_register() {
+    let blocks = lower(
+        r#"
+define void @swap(i32 %a0, i32 %b0, i32 %n) {
+entry:
+  br label %loop
+
+loop:
+  %a = phi i32 [ %a0, %entry ], [ %b, %loop ]
+  %b = phi i32 [ %b0, %entry ], [ %a, %loop ]
+  %i = phi i32 [ 0, %entry ], [ %i1, %loop ]
+  %i1 = add i32 %i, 1
+  %done = icmp eq i32 %i1, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+"#,
+    );
+
+    assert!(!has_phis(&blocks));
+    // The back edge is critical, so its copies live in a new block.
+    let split = &blocks
+        .iter()
+        .find(|b| b.name.starts_with("loop_loop"))
+        .expect("critical edge not split")
+        .name;
+    assert_eq!(
+        copies(&blocks, split),
+        vec![
+            ("i".into(), "i1".into()),
+            ("a$swap".into(), "a".into()),
+            ("a".into(), "b".into()),
+            ("b".into(), "a$swap".into()),
+        ]
+    );
+
+    let loop_block = &blocks.iter().find(|b| b.name == "loop").unwrap().instrs;
+    match loop_block.last() {
+        Some(Instruction::CondBr { else_target, .. }) => assert_eq!(else_target, split),
+        other => panic!("unexpected terminator {other:?}"),
+    }
+}
+
+#[test]
+fn critical_edges_are_split() {
+    let blocks = lower(
+        r#"
+define i32 @lost(i1 %c, i32 %x) {
+entry:
+  br i1 %c, label %join, label %other
+
+other:
+  %y = add i32 %x, 1
+  br label %join
+
+join:
+  %v = phi i32 [ %x, %entry ], [ %y, %other ]
+  ret i32 %v
+}
+"#,
+    );
+
+    assert!(copies(&blocks, "entry").is_empty());
+    assert_eq!(
+        copies(&blocks, "entry_join"),
+        vec![("v".into(), "x".into())]
+    );
+    assert_eq!(copies(&blocks, "other"), vec![("v".into(), "y".into())]);
+
+    let entry = &blocks.iter().find(|b| b.name == "entry").unwrap().instrs;
+    match entry.last() {
+        Some(Instruction::CondBr { then_target, .. }) => assert_eq!(then_target, "entry_join"),
+        other => panic!("unexpected terminator {other:?}"),
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ptx_backend::addr_space::AddressSpace;
+use ptx_backend::ast::{Instruction, Operand, Statement, VarDecl};
+use ptx_backend::printer::print_statements;
+use ptx_backend::ptx_type::PTXType;
+
+#[test]
+fn instruction_with_modifiers_and_address() {
+    let instr = Instruction::new(
+        "ld",
+        &["global", "f32"],
+        vec![
+            Operand::reg("x"),
+            Operand::Addr(Box::new(Operand::reg("p")), 8),
+        ],
+    );
+    assert_eq!(instr.to_string(), "ld.global.f32 %x, [%p+8];");
+}
+
+#[test]
+fn float_immediates_use_hex_bits() {
+    assert_eq!(Operand::F32(1.0).to_string(), "0f3F800000");
+    assert_eq!(Operand::F64(-2.0).to_string(), "0dC000000000000000");
+}
+
+#[test]
+fn guarded_instruction_prints_predicate() {
+    let instr =
+        Instruction::new("bra", &[], vec![Operand::symbol("exit")]).guarded(&Operand::reg("p"));
+    assert_eq!(instr.to_string(), "@%p bra exit;");
+}
+
+#[test]
+fn var_decl_with_alignment_and_length() {
+    let decl = VarDecl {
+        space: AddressSpace::Shared,
+        align: Some(16),
+        ty: "b8".to_string(),
+        name: "tile".to_string(),
+        len: Some(256),
+        init: vec![],
+    };
+    assert_eq!(decl.to_string(), ".shared .align 16 .b8 tile[256]");
+}
+
+#[test]
+fn var_decl_with_initializer() {
+    let decl = VarDecl {
+        space: AddressSpace::Global,
+        align: Some(1),
+        ty: "b8".to_string(),
+        name: "msg".to_string(),
+        len: Some(3),
+        init: vec![Operand::Imm(104), Operand::Imm(105), Operand::Imm(0)],
+    };
+    assert_eq!(
+        decl.to_string(),
+        ".global .align 1 .b8 msg[3] = {104, 105, 0}"
+    );
+}
+
+#[test]
+fn statements_are_indented_by_depth() {
+    let body = vec![
+        Statement::Regs(PTXType::S32, vec!["a".to_string(), "b".to_string()]),
+        Statement::Label("entry".to_string()),
+        Instruction::new(
+            "add",
+            &["s32"],
+            vec![Operand::reg("a"), Operand::reg("b"), Operand::Imm(1)],
+        )
+        .into(),
+        Statement::Scope(vec![Statement::Comment("inner".to_string())]),
+    ];
+
+    assert_eq!(
+        print_statements(&body, 1),
+        vec![
+            ".reg .s32 %a, %b;",
+            "entry:",
+            "    add.s32 %a, %b, 1;",
+            "    {",
+            "        // inner",
+            "    }",
+        ]
+    );
+}
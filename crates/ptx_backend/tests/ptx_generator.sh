@@ -17,7 +17,7 @@
 
 cd crates/ptx_backend
 
-for file in inputs/*.ll; do
+for file in tests/inputs/*.ll; do
     name=$(basename "$file" .ll)
-    cargo run --bin ptx-backend -- "$file" > "snapshots/$name.ptx"
+    cargo run --quiet --bin ptx-backend -- "$file" > "tests/snapshots/$name.ptx"
 done
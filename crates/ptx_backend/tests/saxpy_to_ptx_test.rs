@@ -16,8 +16,11 @@
 // limitations under the License.
 
 use insta::assert_snapshot;
+use ir_model::{BasicBlock, CallingConv, Function};
 use llvm_parser::parse_module::parse_module;
+use ptx_backend::data_layout::DataLayout;
 use ptx_backend::lower_function;
+use ptx_backend::module::CodegenOptions;
 
 #[test]
 fn test_saxpy_to_ptx() {
@@ -35,7 +38,10 @@ fn test_saxpy_to_ptx() {
     actual.push_str(".target sm_75\n");
     actual.push_str(".address_size 64\n\n");
 
+    let layout = DataLayout::from_module(&module);
+
     for func in module.functions {
+        let signature = llvm_parser::lower_signature(&func.parameters, &func.return_type);
         let instrs = func
             .basic_blocks
             .iter()
@@ -44,14 +50,25 @@ fn test_saxpy_to_ptx() {
                 let lowered = bb
                     .instrs
                     .iter()
-                    .map(|instr| llvm_parser::convert::lower(&func.name, instr))
+                    .map(|instr| llvm_parser::convert::lower(instr, &module.types))
                     .collect();
-                (name, lowered)
+                BasicBlock::new(name, lowered)
             })
             .collect::<Vec<_>>();
 
-        let lines = lower_function(&func.name, &instrs, "sm_75");
-        for line in lines {
+        let function = Function {
+            name: func.name.clone(),
+            signature,
+            attributes: vec![],
+            kernel: true,
+            calling_conv: CallingConv::C,
+            internal: false,
+            blocks: instrs,
+        };
+
+        let function = lower_function(&function, &[], &layout, &CodegenOptions::default())
+            .expect("lowering failed");
+        for line in function.lines() {
             actual.push_str(&format!("{}\n", line));
         }
         actual.push('\n');
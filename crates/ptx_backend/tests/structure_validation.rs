@@ -15,11 +15,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use ir_model::Instruction;
-use llvm_parser::convert::lower;
+use ir_model::{BasicBlock, CallingConv, Function, Instruction};
+use llvm_parser::convert::{lower, name_to_string};
 use llvm_parser::parse_llvm_ir_from_str;
 use llvm_parser::parse_module::parse_module;
+use ptx_backend::data_layout::DataLayout;
 use ptx_backend::lower_function;
+use ptx_backend::module::{CodegenOptions, PtxModule};
 
 fn check_single_entry(ptx: &str) -> bool {
     ptx.matches(".entry ").count() == 1
@@ -49,10 +51,10 @@ fn check_register_collisions(ptx: &str) -> bool {
             .map(|r| r.trim_start_matches('%').trim());
 
         for r in regs {
-            if let Some(prev) = map.insert(r.to_string(), reg_type.to_string()) {
-                if prev != reg_type {
-                    return false; // type conflict
-                }
+            if let Some(prev) = map.insert(r.to_string(), reg_type.to_string())
+                && prev != reg_type
+            {
+                return false; // type conflict
             }
         }
     }
@@ -86,9 +88,12 @@ fn validate_structural_rules() {
 
     let module = parse_module(&path).expect("Failed to parse module");
 
-    let mut ptx = String::new();
+    let mut module_ptx = PtxModule::default();
+
+    let layout = DataLayout::from_module(&module);
 
     for func in module.functions {
+        let signature = llvm_parser::lower_signature(&func.parameters, &func.return_type);
         let all_instrs = func
             .basic_blocks
             .into_iter()
@@ -96,17 +101,28 @@ fn validate_structural_rules() {
                 let instrs = block
                     .instrs
                     .iter()
-                    .map(|instr| lower(&func.name, instr))
+                    .map(|instr| lower(instr, &module.types))
                     .collect::<Vec<_>>();
-                (block.name.to_string(), instrs)
+                BasicBlock::new(name_to_string(&block.name), instrs)
             })
             .collect::<Vec<_>>();
 
-        for line in lower_function(&func.name, &all_instrs, "sm_75") {
-            ptx.push_str(&line);
-            ptx.push('\n');
-        }
+        let function = Function {
+            name: func.name.clone(),
+            signature,
+            attributes: vec![],
+            kernel: true,
+            calling_conv: CallingConv::C,
+            internal: false,
+            blocks: all_instrs,
+        };
+
+        module_ptx.add_function(
+            lower_function(&function, &[], &layout, &CodegenOptions::default())
+                .expect("lowering failed"),
+        );
     }
+    let ptx = module_ptx.to_string();
 
     assert!(check_single_entry(&ptx), "multiple .entry sections");
     assert!(check_single_header(&ptx), "redundant header declarations");
@@ -127,13 +143,13 @@ fn no_unhandled_instructions() {
     let mut all_instrs = vec![];
 
     for func in &module.functions {
-        let blocks = llvm_parser::lower(func).unwrap();
+        let blocks = llvm_parser::lower(func, &module.types).unwrap();
         all_instrs.extend(blocks);
     }
 
     let flat: Vec<_> = all_instrs
         .iter()
-        .flat_map(|(_, instrs)| instrs.iter())
+        .flat_map(|block| block.instrs.iter())
         .collect();
     let count = flat
         .iter()
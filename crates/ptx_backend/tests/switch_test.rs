@@ -0,0 +1,80 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir_model::{Type, Value};
+use ptx_backend::compile_module;
+use ptx_backend::module::{CodegenOptions, OnUnreachable};
+use ptx_backend::switch::jump_table;
+
+fn case(value: u64, target: &str) -> (Value, String) {
+    (
+        Value::ConstInt {
+            ty: Type::Int(32),
+            value,
+        },
+        target.to_string(),
+    )
+}
+
+fn x() -> Value {
+    Value::Local {
+        name: "%x".to_string(),
+        ty: Type::Int(32),
+    }
+}
+
+#[test]
+fn dense_switch_gets_a_table_with_holes_on_the_default() {
+    let cases = [case(4, "a"), case(5, "b"), case(7, "c"), case(8, "d")];
+    let table = jump_table(&x(), "def", &cases).unwrap();
+
+    assert_eq!(table.low, 4);
+    assert_eq!(table.targets, ["a", "b", "def", "c", "d"]);
+}
+
+#[test]
+fn sparse_or_short_switch_uses_a_chain() {
+    let sparse = [case(0, "a"), case(10, "b"), case(20, "c"), case(30, "d")];
+    assert_eq!(jump_table(&x(), "def", &sparse), None);
+
+    let short = [case(0, "a"), case(1, "b"), case(2, "c")];
+    assert_eq!(jump_table(&x(), "def", &short), None);
+}
+
+const UNREACHABLE: &str = r#"
+define void @k(i32* %out) {
+entry:
+  store i32 0, i32* %out, align 4
+  unreachable
+}
+"#;
+
+#[test]
+fn unreachable_follows_the_option() {
+    let module = llvm_parser::parse_llvm_ir_from_str(UNREACHABLE).unwrap();
+    let trap = compile_module(&module, &CodegenOptions::default())
+        .unwrap()
+        .to_string();
+    let options = CodegenOptions {
+        unreachable: OnUnreachable::Exit,
+        ..Default::default()
+    };
+    let exit = compile_module(&module, &options).unwrap().to_string();
+
+    assert!(trap.contains("    trap;\n}"), "{trap}");
+    assert!(exit.contains("    exit;\n}"), "{exit}");
+}
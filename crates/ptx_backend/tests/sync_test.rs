@@ -0,0 +1,83 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir_model::{BarrierKind, Instruction, MemoryOrdering, MemoryScope, Type, Value};
+use ptx_backend::addr_space::AddressSpaces;
+use ptx_backend::data_layout::DataLayout;
+use ptx_backend::to_ptx;
+use ptx_backend::type_map::TypeMap;
+
+fn lower(instr: Instruction) -> String {
+    to_ptx(
+        &instr,
+        &TypeMap::new(),
+        &AddressSpaces::default(),
+        &DataLayout::default(),
+    )
+}
+
+fn fence(ordering: MemoryOrdering, scope: MemoryScope) -> String {
+    lower(Instruction::Fence { ordering, scope })
+}
+
+#[test]
+fn fences_pick_semantics_and_scope() {
+    assert_eq!(
+        fence(MemoryOrdering::AcqRel, MemoryScope::Device),
+        "fence.acq_rel.gpu;"
+    );
+    assert_eq!(
+        fence(MemoryOrdering::Acquire, MemoryScope::Block),
+        "fence.acq_rel.cta;"
+    );
+    assert_eq!(
+        fence(MemoryOrdering::SeqCst, MemoryScope::System),
+        "fence.sc.sys;"
+    );
+}
+
+#[test]
+fn membar_levels() {
+    let membar = |scope| lower(Instruction::MemBar { scope });
+    assert_eq!(membar(MemoryScope::Block), "membar.cta;");
+    assert_eq!(membar(MemoryScope::Device), "membar.gl;");
+    assert_eq!(membar(MemoryScope::System), "membar.sys;");
+}
+
+#[test]
+fn barriers_with_register_counts() {
+    let id = Value::ConstInt {
+        value: 3,
+        ty: Type::Int(32),
+    };
+    let count = Value::Local {
+        name: "n".into(),
+        ty: Type::Int(32),
+    };
+    let barrier = |kind, aligned| {
+        lower(Instruction::Barrier {
+            kind,
+            aligned,
+            id: id.clone(),
+            count: Some(count.clone()),
+        })
+    };
+
+    assert_eq!(barrier(BarrierKind::Sync, true), "bar.sync 3, %n;");
+    assert_eq!(barrier(BarrierKind::Sync, false), "barrier.sync 3, %n;");
+    assert_eq!(barrier(BarrierKind::Arrive, true), "bar.arrive 3, %n;");
+}
@@ -0,0 +1,85 @@
+// Copyright 2025 Raul Estrada <restrada@treutech.io>
+// SPDX-License-Identifier: Apache-2.0
+//
+// This file is part of the PTXGEN-RS project by Treu Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir_model::{BasicBlock, Instruction, Type, Value};
+use llvm_parser::parse_llvm_ir_from_str;
+use ptx_backend::ptx_type::PTXType;
+use ptx_backend::type_infer::infer_types;
+
+const MIXED_LL: &str = r#"
+define void @mixed(double* %p, i64 %n, i8 %c) {
+entry:
+  %v = load double, double* %p
+  %w = fadd double %v, 1.0
+  %m = add i64 %n, 1
+  %z = zext i8 %c to i32
+  %lt = icmp slt i64 %m, 10
+  ret void
+}
+"#;
+
+#[test]
+fn types_come_from_llvm_not_names() {
+    let module = parse_llvm_ir_from_str(MIXED_LL).unwrap();
+    let blocks = llvm_parser::lower(&module.functions[0], &module.types).unwrap();
+    let map = infer_types(&blocks).expect("inference failed");
+
+    assert_eq!(map.get("p"), Some(&PTXType::Ptr));
+    assert_eq!(map.get("v"), Some(&PTXType::F64));
+    assert_eq!(map.get("w"), Some(&PTXType::F64));
+    assert_eq!(map.get("n"), Some(&PTXType::S64));
+    assert_eq!(map.get("m"), Some(&PTXType::S64));
+    assert_eq!(map.get("c"), Some(&PTXType::S16));
+    assert_eq!(map.get("z"), Some(&PTXType::S32));
+    assert_eq!(map.get("lt"), Some(&PTXType::Pred));
+}
+
+#[test]
+fn memory_types_keep_narrow_widths() {
+    assert_eq!(PTXType::from_ir(&Type::Int(8)), Some(PTXType::S16));
+    assert_eq!(PTXType::memory_from_ir(&Type::Int(8)), Some(PTXType::S8));
+    assert_eq!(PTXType::from_ir(&Type::Int(1)), Some(PTXType::Pred));
+    assert_eq!(PTXType::memory_from_ir(&Type::Int(1)), Some(PTXType::U8));
+    assert_eq!(PTXType::memory_from_ir(&Type::Int(16)), Some(PTXType::S16));
+    assert_eq!(PTXType::memory_from_ir(&Type::Float), Some(PTXType::F32));
+}
+
+#[test]
+fn conflicting_register_types_are_an_error() {
+    let local = |name: &str, ty: Type| Value::Local {
+        name: name.into(),
+        ty,
+    };
+    let blocks = vec![BasicBlock::new(
+        "entry",
+        vec![
+            Instruction::Add {
+                dst: local("x", Type::Int(32)),
+                lhs: local("a", Type::Int(32)),
+                rhs: local("a", Type::Int(32)),
+            },
+            Instruction::FAdd {
+                dst: local("x", Type::Float),
+                lhs: local("b", Type::Float),
+                rhs: local("b", Type::Float),
+            },
+        ],
+    )];
+
+    let err = infer_types(&blocks).expect_err("conflict not detected");
+    assert!(err.to_string().contains("%x"), "unexpected error: {err}");
+}
@@ -15,7 +15,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use ir_model::Instruction;
+use ir_model::{Instruction, Type, Value};
+use ptx_backend::addr_space::AddressSpaces;
+use ptx_backend::data_layout::DataLayout;
 use ptx_backend::to_ptx;
 use ptx_backend::type_map::declare_registers_from_typemap;
 
@@ -26,13 +28,17 @@ use common::build_typemap;
 fn test_unhandled_instruction_output() {
     let instrs = vec![
         Instruction::Alloca {
-            function: "test".into(),
-            dst: "%i".into(),
-            ty: "i32".into(),
+            dst: Value::Local {
+                name: "i".into(),
+                ty: Type::Ptr {
+                    pointee: Box::new(Type::Int(32)),
+                    addr_space: 0,
+                },
+            },
+            ty: Type::Int(32),
             align: 0,
         },
         Instruction::Unhandled {
-            function: "test".into(),
             text: "foobar %a, %b".into(),
         },
     ];
@@ -55,7 +61,15 @@ fn test_unhandled_instruction_output() {
 
     actual.push_str(".entry test {\n");
     for instr in &instrs {
-        actual.push_str(&format!("    {}\n", to_ptx(instr, &type_map)));
+        actual.push_str(&format!(
+            "    {}\n",
+            to_ptx(
+                instr,
+                &type_map,
+                &AddressSpaces::default(),
+                &DataLayout::default()
+            )
+        ));
     }
     actual.push_str("}\n\n");
 